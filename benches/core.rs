@@ -0,0 +1,68 @@
+use {
+    criterion::{criterion_group, criterion_main, Criterion},
+    vx::core::*,
+};
+
+struct Root;
+
+impl ComponentFactory for Root {
+    fn new(_globals: &mut Globals, _cref: ComponentRef<Self>) -> Self {
+        Root
+    }
+}
+
+impl Component for Root {}
+
+struct Leaf;
+
+impl ComponentFactory for Leaf {
+    fn new(_globals: &mut Globals, _cref: ComponentRef<Self>) -> Self {
+        Leaf
+    }
+}
+
+impl Component for Leaf {}
+
+fn globals() -> (Globals, ComponentRef<Root>) {
+    Globals::new(vx::theme::flat::FlatTheme)
+}
+
+fn tree_mutation(c: &mut Criterion) {
+    let (mut globals, root) = globals();
+    c.bench_function("mount and unmount 1k children", |b| {
+        b.iter(|| {
+            let children: Vec<ComponentRef<Leaf>> =
+                (0..1000).map(|_| globals.child(root)).collect();
+            for child in children {
+                globals.unmount(child);
+            }
+        })
+    });
+}
+
+fn deep_propagation(c: &mut Criterion) {
+    let (mut globals, root) = globals();
+    let mut parent: ComponentRef<Leaf> = globals.child(root);
+    for _ in 1..500 {
+        parent = globals.child(parent);
+    }
+
+    c.bench_function("propagate update 500 deep", |b| {
+        b.iter(|| globals.update(root, Repaint::Yes, Propagate::Yes))
+    });
+}
+
+fn wide_emission(c: &mut Criterion) {
+    let (mut globals, root) = globals();
+    let signal = globals.signal::<u32>();
+    for _ in 0..10_000 {
+        globals.listen(signal, root, |_, _| {});
+    }
+
+    c.bench_function("emit to 10k listeners", |b| {
+        b.iter(|| globals.emit(signal, &0))
+    });
+}
+
+criterion_group!(benches, tree_mutation, deep_propagation, wide_emission);
+criterion_main!(benches);
@@ -7,10 +7,14 @@ impl vx::core::ComponentFactory for Counter {
     fn new(globals: &mut vx::core::Globals, cref: vx::core::ComponentRef<Self>) -> Self {
         let btn: vx::kit::ButtonRef = globals.child(cref);
 
-        globals.listen(globals.get(btn).on_click, cref, move |globals, _| {
-            globals.get_mut(cref).count += 1;
-            globals.update(cref, vx::core::Repaint::No, vx::core::Propagate::No);
-        });
+        globals.connect(
+            globals.get(btn).on_click,
+            cref,
+            move |counter, globals, _| {
+                counter.count += 1;
+                globals.update(cref, vx::core::Repaint::No, vx::core::Propagate::No);
+            },
+        );
 
         Counter { count: 0, btn }
     }
@@ -33,6 +37,9 @@ fn main() {
     globals.update(root, Default::default(), Default::default());
 
     for _ in 0..1000 {
-        globals.emit(globals.get(globals.get(root).btn).on_click, &());
+        globals.emit(
+            globals.get(globals.get(root).btn).on_click,
+            &vx::kit::ClickEvent::default(),
+        );
     }
 }
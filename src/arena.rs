@@ -0,0 +1,117 @@
+//! Generational arena used to store nodes and signals.
+//!
+//! IDs pack a slot index (low 32 bits) and the slot's generation (high 32 bits).
+//! Removing a value bumps the generation of its slot, so stale IDs never resolve to a newer value occupying the same slot.
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+pub(crate) struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+#[inline]
+fn split(id: u64) -> (usize, u32) {
+    ((id & 0xFFFF_FFFF) as usize, (id >> 32) as u32)
+}
+
+#[inline]
+fn join(index: u32, generation: u32) -> u64 {
+    (u64::from(generation) << 32) | u64::from(index)
+}
+
+impl<T> Arena<T> {
+    /// Inserts a value, returning its newly allocated ID.
+    pub fn insert(&mut self, value: T) -> u64 {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.value = Some(value);
+            join(index, slot.generation)
+        } else {
+            let index = self.slots.len() as u32;
+            self.slots.push(Slot {
+                generation: 0,
+                value: Some(value),
+            });
+            join(index, 0)
+        }
+    }
+
+    /// Removes and returns the value behind `id`, if `id` is still live.
+    pub fn remove(&mut self, id: u64) -> Option<T> {
+        let (index, generation) = split(id);
+        let slot = self.slots.get_mut(index)?;
+        if slot.generation != generation {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index as u32);
+        Some(value)
+    }
+
+    #[inline]
+    pub fn get(&self, id: u64) -> Option<&T> {
+        let (index, generation) = split(id);
+        match self.slots.get(index) {
+            Some(slot) if slot.generation == generation => slot.value.as_ref(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut T> {
+        let (index, generation) = split(id);
+        match self.slots.get_mut(index) {
+            Some(slot) if slot.generation == generation => slot.value.as_mut(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, id: u64) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Iterates over the IDs of all live values.
+    pub fn ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.value
+                .as_ref()
+                .map(|_| join(index as u32, slot.generation))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_ids_are_rejected() {
+        let mut arena = Arena::default();
+        let a = arena.insert("a");
+        assert_eq!(arena.remove(a), Some("a"));
+
+        let b = arena.insert("b");
+        assert_eq!(split(a).0, split(b).0);
+        assert_ne!(a, b);
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.get_mut(a), None);
+        assert_eq!(arena.remove(a), None);
+        assert_eq!(arena.get(b), Some(&"b"));
+        assert_eq!(arena.ids().collect::<Vec<_>>(), vec![b]);
+    }
+}
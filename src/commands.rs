@@ -0,0 +1,100 @@
+//! Undo/redo support built around reversible [`Command`](Command)s.
+//!
+//! Commands are executed through [`Globals::execute`](crate::core::Globals::execute), which records them in the undo stack owned by `Globals`.
+
+use crate::core;
+
+/// A reversible operation.
+pub trait Command {
+    /// Performs the command.
+    ///
+    /// This is invoked when the command is first executed, and again every time it is redone.
+    fn apply(&mut self, globals: &mut core::Globals);
+
+    /// Reverts the effects of [`apply`](Command::apply).
+    fn undo(&mut self, globals: &mut core::Globals);
+}
+
+/// Commands undone and redone as a single unit.
+pub(crate) type Group = Vec<Box<dyn Command>>;
+
+/// History of executed commands.
+#[derive(Default)]
+pub struct UndoStack {
+    done: Vec<Group>,
+    undone: Vec<Group>,
+    transaction: Group,
+    depth: usize,
+}
+
+impl UndoStack {
+    /// Returns `true` if there is a command to undo.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    /// Returns `true` if there is a command to redo.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Returns `true` if a transaction is in progress.
+    #[inline]
+    pub fn in_transaction(&self) -> bool {
+        self.depth > 0
+    }
+
+    pub(crate) fn push(&mut self, command: Box<dyn Command>) {
+        self.undone.clear();
+        if self.in_transaction() {
+            self.transaction.push(command);
+        } else {
+            self.done.push(vec![command]);
+        }
+    }
+
+    pub(crate) fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    pub(crate) fn end(&mut self) {
+        assert!(self.in_transaction(), "no transaction in progress");
+        self.depth -= 1;
+        if self.depth == 0 && !self.transaction.is_empty() {
+            self.done.push(std::mem::take(&mut self.transaction));
+        }
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<Group> {
+        if self.in_transaction() {
+            return None;
+        }
+        self.done.pop()
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<Group> {
+        if self.in_transaction() {
+            return None;
+        }
+        self.undone.pop()
+    }
+
+    #[inline]
+    pub(crate) fn push_done(&mut self, group: Group) {
+        self.done.push(group);
+    }
+
+    #[inline]
+    pub(crate) fn push_undone(&mut self, group: Group) {
+        self.undone.push(group);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+        self.transaction.clear();
+        self.depth = 0;
+    }
+}
@@ -1,7 +1,13 @@
 use {
-    crate::{signal, theme},
+    crate::{arena::Arena, commands, perf, signal, theme},
     reclutch::display as gfx,
-    std::{any::Any, collections::HashMap, rc::Rc},
+    std::{
+        any::{Any, TypeId},
+        collections::{BTreeMap, HashMap, HashSet},
+        rc::Rc,
+        sync::{Arc, Mutex},
+    },
+    thiserror::Error,
 };
 
 /// Core component trait, implemented by all distinct elements of a UI.
@@ -27,6 +33,7 @@ pub trait Component: AsBoxAny + 'static {
     /// Invoked during rendering.
     ///
     /// This should return a list of display commands that should be used to display this component.
+    /// The result is cached until the component is next updated with [`Repaint::Yes`](Repaint::Yes).
     #[inline]
     fn display(&mut self) -> Vec<gfx::DisplayCommand> {
         Default::default()
@@ -36,9 +43,40 @@ pub trait Component: AsBoxAny + 'static {
     ///
     /// Update logic should be placed here.
     ///
-    /// Do not emit any events here.
+    /// Do not emit any events here (this is enforced when debug assertions are enabled).
     #[inline]
     fn update(&mut self, _globals: &mut Globals) {}
+
+    /// Invoked once the component has been constructed and is fully registered in the tree.
+    ///
+    /// This mirrors [`unmount`](Component::unmount); any setup which relies on the node being complete (e.g. emitting
+    /// signals whose listeners query the tree) belongs here rather than in [`ComponentFactory::new`](ComponentFactory::new).
+    #[inline]
+    fn mounted(&mut self, _globals: &mut Globals) {}
+
+    /// Invoked by [`Globals::send`](Globals::send) with a message of any type.
+    ///
+    /// Downcast `message` to the types this component understands and ignore the rest.
+    #[inline]
+    fn handle_message(&mut self, _globals: &mut Globals, _message: &dyn Any) {}
+
+    /// Invoked by [`Globals::run_frame`](Globals::run_frame) for each phase the component was [scheduled](Globals::schedule) for.
+    #[inline]
+    fn run_phase(&mut self, _globals: &mut Globals, _phase: Phase) {}
+
+    /// Invoked by [`Globals::persist_to`](Globals::persist_to) to capture state which should survive restarts.
+    ///
+    /// Returning `None` (the default) opts out of persistence.
+    #[cfg(feature = "persistence")]
+    #[inline]
+    fn save_state(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Invoked by [`Globals::restore_from`](Globals::restore_from) with the state previously returned by [`save_state`](Component::save_state).
+    #[cfg(feature = "persistence")]
+    #[inline]
+    fn restore_state(&mut self, _state: serde_json::Value) {}
 }
 
 impl<C: Component> AsBoxAny for C {
@@ -56,6 +94,16 @@ pub trait ComponentFactory: Sized + Component {
     fn new(globals: &mut Globals, cref: ComponentRef<Self>) -> Self;
 }
 
+/// Implemented by components capable of constructing themselves from a set of initial properties.
+///
+/// This avoids a mutate-after-create sequence (and the updates it triggers) when a component should start in a non-default state.
+pub trait ComponentFactoryWith<P>: Sized + Component {
+    /// Constructs a new component of type `Self`, configured by `props`.
+    ///
+    /// `cref` is the reference to self component within `globals`.
+    fn new_with(globals: &mut Globals, cref: ComponentRef<Self>, props: P) -> Self;
+}
+
 /// Strongly-typed reference to a component.
 #[derive(Derivative)]
 #[derivative(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -68,7 +116,31 @@ pub trait ComponentFactory: Sized + Component {
 #[derivative(PartialOrd(bound = ""))]
 #[derivative(Ord(bound = ""))]
 #[derivative(Hash(bound = ""))]
-pub struct ComponentRef<T: Component>(u64, std::marker::PhantomData<T>);
+// `fn() -> T` so that references are `Send` and `Sync` regardless of `T`
+pub struct ComponentRef<T: Component>(u64, std::marker::PhantomData<fn() -> T>);
+
+impl<T: Component> ComponentRef<T> {
+    /// Creates a weak reference to the same component.
+    #[inline]
+    pub fn downgrade(self) -> WeakComponentRef<T> {
+        WeakComponentRef(self.0, Default::default())
+    }
+}
+
+/// Weak reference to a component, meant to be stored in long-lived closures.
+///
+/// It must be [upgraded](Globals::upgrade) before use, which fails once the component is unmounted.
+#[derive(Derivative)]
+#[derivative(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derivative(Debug(bound = ""))]
+#[derivative(Clone(bound = ""))]
+#[derivative(Copy(bound = ""))]
+#[derivative(PartialEq(bound = ""))]
+#[derivative(Eq(bound = ""))]
+#[derivative(PartialOrd(bound = ""))]
+#[derivative(Ord(bound = ""))]
+#[derivative(Hash(bound = ""))]
+pub struct WeakComponentRef<T: Component>(u64, std::marker::PhantomData<fn() -> T>);
 
 /// Untyped reference to a component.
 ///
@@ -96,17 +168,64 @@ impl CRef for UntypedComponentRef {
     }
 }
 
+impl<T: Component> From<ComponentRef<T>> for UntypedComponentRef {
+    #[inline]
+    fn from(cref: ComponentRef<T>) -> Self {
+        UntypedComponentRef(cref.0)
+    }
+}
+
 impl UntypedComponentRef {
     /// Attaches a type to the component reference.
     ///
     /// # Warning
     /// Call this sparingly and cautiously. It will cause a `panic` if an incorrect type is provided.
+    #[deprecated(note = "use `try_typed`, which verifies the type of the node")]
     #[inline]
     pub fn to_typed<T: Component>(self) -> ComponentRef<T> {
+        self.typed_unchecked()
+    }
+
+    /// Attaches a type to the component reference, verifying that the node is actually of that type.
+    ///
+    /// Returns `None` if the type doesn't match or the reference is invalid.
+    #[inline]
+    pub fn try_to_typed<T: Component>(self, globals: &Globals) -> Option<ComponentRef<T>> {
+        let cref = self.typed_unchecked();
+        if globals.is_of_type(cref) {
+            Some(cref)
+        } else {
+            None
+        }
+    }
+
+    /// Alias of [`try_to_typed`](UntypedComponentRef::try_to_typed).
+    #[inline]
+    pub fn try_typed<T: Component>(self, globals: &Globals) -> Option<ComponentRef<T>> {
+        self.try_to_typed(globals)
+    }
+
+    #[inline]
+    pub(crate) fn typed_unchecked<T: Component>(self) -> ComponentRef<T> {
         ComponentRef(self.0, Default::default())
     }
 }
 
+/// Subtree lifted out of the tree by [`Globals::detach`](Globals::detach), still mounted but without a parent.
+///
+/// Hand it back to [`Globals::attach`](Globals::attach) to put it somewhere in the tree again.
+/// Dropping it leaves the subtree alive as a separate root; unmount [`root`](DetachedSubtree::root) to dispose of it.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct DetachedSubtree(UntypedComponentRef);
+
+impl DetachedSubtree {
+    /// Returns the root of the detached subtree.
+    #[inline]
+    pub fn root(&self) -> UntypedComponentRef {
+        self.0
+    }
+}
+
 #[doc(hidden)]
 pub trait AsBoxAny {
     fn as_box_any(self: Box<Self>) -> Box<dyn Any>;
@@ -133,7 +252,29 @@ trait InternalNode: Node {
 
     fn detach_listeners(&mut self, globals: &mut Globals);
     fn repaint(&mut self);
-    fn push_child(&mut self, child: UntypedComponentRef);
+    fn children_mut(&mut self) -> &mut Vec<UntypedComponentRef>;
+    fn extend_children(&mut self, children: &[UntypedComponentRef]);
+    fn remove_child(&mut self, child: UntypedComponentRef);
+    fn set_parent(&mut self, parent: UntypedComponentRef);
+    fn listener_signals(&self) -> Vec<u64>;
+    fn listener_count(&self) -> usize;
+    fn forget_signal(&mut self, signal: u64);
+    fn own_signal(&mut self, signal: u64);
+    fn owned_signals(&self) -> &[u64];
+    fn type_name(&self) -> &'static str;
+    fn props(&self) -> &HashMap<&'static str, Box<dyn Any>>;
+    fn props_mut(&mut self) -> &mut HashMap<&'static str, Box<dyn Any>>;
+    fn contexts(&self) -> &HashMap<TypeId, Box<dyn Any>>;
+    fn contexts_mut(&mut self) -> &mut HashMap<TypeId, Box<dyn Any>>;
+    fn meta(&self) -> &HashMap<String, Box<dyn Any>>;
+    fn meta_mut(&mut self) -> &mut HashMap<String, Box<dyn Any>>;
+    fn cached_display(&self) -> Option<&[gfx::DisplayCommand]>;
+    fn cache_display(&mut self, cmds: Vec<gfx::DisplayCommand>);
+
+    #[cfg(feature = "persistence")]
+    fn save_state(&self) -> Option<serde_json::Value>;
+    #[cfg(feature = "persistence")]
+    fn restore_state(&mut self, state: serde_json::Value);
 }
 
 impl<T: Component> InternalNode for ComponentNode<T> {
@@ -182,11 +323,110 @@ impl<T: Component> InternalNode for ComponentNode<T> {
     #[inline]
     fn repaint(&mut self) {
         self.cmds.repaint();
+        self.display = None;
+    }
+
+    #[inline]
+    fn children_mut(&mut self) -> &mut Vec<UntypedComponentRef> {
+        &mut self.children
+    }
+
+    #[inline]
+    fn extend_children(&mut self, children: &[UntypedComponentRef]) {
+        self.children.extend_from_slice(children);
+    }
+
+    #[inline]
+    fn remove_child(&mut self, child: UntypedComponentRef) {
+        self.children.retain(|x| *x != child);
+    }
+
+    #[inline]
+    fn set_parent(&mut self, parent: UntypedComponentRef) {
+        self.parent = parent;
+    }
+
+    fn listener_signals(&self) -> Vec<u64> {
+        self.listeners.iter().map(|x| x.signal).collect()
+    }
+
+    #[inline]
+    fn listener_count(&self) -> usize {
+        self.listeners.len()
+    }
+
+    #[inline]
+    fn forget_signal(&mut self, signal: u64) {
+        self.listeners.retain(|x| x.signal != signal);
+    }
+
+    #[inline]
+    fn own_signal(&mut self, signal: u64) {
+        self.signals.push(signal);
+    }
+
+    #[inline]
+    fn owned_signals(&self) -> &[u64] {
+        &self.signals
+    }
+
+    #[inline]
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    #[inline]
+    fn props(&self) -> &HashMap<&'static str, Box<dyn Any>> {
+        &self.props
+    }
+
+    #[inline]
+    fn props_mut(&mut self) -> &mut HashMap<&'static str, Box<dyn Any>> {
+        &mut self.props
+    }
+
+    #[inline]
+    fn contexts(&self) -> &HashMap<TypeId, Box<dyn Any>> {
+        &self.contexts
+    }
+
+    #[inline]
+    fn contexts_mut(&mut self) -> &mut HashMap<TypeId, Box<dyn Any>> {
+        &mut self.contexts
+    }
+
+    #[inline]
+    fn meta(&self) -> &HashMap<String, Box<dyn Any>> {
+        &self.meta
+    }
+
+    #[inline]
+    fn meta_mut(&mut self) -> &mut HashMap<String, Box<dyn Any>> {
+        &mut self.meta
+    }
+
+    #[inline]
+    fn cached_display(&self) -> Option<&[gfx::DisplayCommand]> {
+        self.display.as_deref()
+    }
+
+    #[inline]
+    fn cache_display(&mut self, cmds: Vec<gfx::DisplayCommand>) {
+        self.display = Some(cmds);
     }
 
+    #[cfg(feature = "persistence")]
     #[inline]
-    fn push_child(&mut self, child: UntypedComponentRef) {
-        self.children.push(child);
+    fn save_state(&self) -> Option<serde_json::Value> {
+        self.component.as_ref()?.save_state()
+    }
+
+    #[cfg(feature = "persistence")]
+    #[inline]
+    fn restore_state(&mut self, state: serde_json::Value) {
+        if let Some(component) = self.component.as_mut() {
+            component.restore_state(state);
+        }
     }
 }
 
@@ -209,10 +449,12 @@ struct ListenerPair {
 
 impl ListenerPair {
     fn detach(&self, globals: &mut Globals) {
-        if let Some(signal) = globals.signal_map.get_mut(&self.signal).unwrap().as_mut() {
-            signal.detach(self.listener);
-        } else {
-            globals.listener_removal.push(self.listener);
+        if let Some(slot) = globals.signal_map.get_mut(self.signal) {
+            if let Some(signal) = slot.signal.as_mut() {
+                signal.detach(self.listener);
+            } else {
+                slot.pending_removal.push(self.listener);
+            }
         }
     }
 }
@@ -224,8 +466,62 @@ pub struct ComponentNode<T: Component> {
     component: Option<T>,
     listeners: Vec<ListenerPair>,
     cmds: gfx::CommandGroup,
+    display: Option<Vec<gfx::DisplayCommand>>,
+    props: HashMap<&'static str, Box<dyn Any>>,
+    contexts: HashMap<TypeId, Box<dyn Any>>,
+    meta: HashMap<String, Box<dyn Any>>,
+    signals: Vec<u64>,
+}
+
+impl<T: Component> ComponentNode<T> {
+    fn new(parent: UntypedComponentRef) -> Self {
+        ComponentNode {
+            parent,
+            children: Vec::new(),
+            component: None,
+            listeners: Vec::new(),
+            cmds: Default::default(),
+            display: None,
+            props: Default::default(),
+            contexts: Default::default(),
+            meta: Default::default(),
+            signals: Vec::new(),
+        }
+    }
+}
+
+/// Typed key of a node property, as used by [`Globals::set_prop`](Globals::set_prop).
+///
+/// Keys are identified by their name and are typically declared as constants, e.g. `const TOOLTIP: PropKey<String> = PropKey::new("tooltip");`.
+#[derive(Derivative)]
+#[derivative(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derivative(Debug(bound = ""))]
+#[derivative(Clone(bound = ""))]
+#[derivative(Copy(bound = ""))]
+#[derivative(PartialEq(bound = ""))]
+#[derivative(Eq(bound = ""))]
+#[derivative(PartialOrd(bound = ""))]
+#[derivative(Ord(bound = ""))]
+#[derivative(Hash(bound = ""))]
+pub struct PropKey<T>(&'static str, std::marker::PhantomData<fn() -> T>);
+
+impl<T> PropKey<T> {
+    #[inline]
+    pub const fn new(name: &'static str) -> Self {
+        PropKey(name, std::marker::PhantomData)
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
 }
 
+/// Node property storing the z-index of a component among its siblings.
+pub const Z_INDEX: PropKey<i32> = PropKey::new("z_index");
+/// Node property storing the tooltip text of a component.
+pub const TOOLTIP: PropKey<String> = PropKey::new("tooltip");
+
 /// Whether a repaint should be scheduled.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Repaint {
@@ -265,6 +561,22 @@ impl Default for Update {
     }
 }
 
+/// Stage of a frame, as executed (in declaration order) by [`Globals::run_frame`](Globals::run_frame).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    /// Sizes and positions are computed.
+    Layout,
+    /// Display commands are rebuilt; the component is repainted once its hook returns.
+    Paint,
+    /// Work which should observe the finished frame (e.g. scrolling something into view).
+    PostFrame,
+}
+
+impl Phase {
+    /// Every phase, in execution order.
+    pub const ALL: [Phase; 3] = [Phase::Layout, Phase::Paint, Phase::PostFrame];
+}
+
 #[derive(Derivative)]
 #[derivative(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[derivative(Debug(bound = ""))]
@@ -275,7 +587,7 @@ impl Default for Update {
 #[derivative(PartialOrd(bound = ""))]
 #[derivative(Ord(bound = ""))]
 #[derivative(Hash(bound = ""))]
-pub struct SignalRef<T>(u64, std::marker::PhantomData<T>);
+pub struct SignalRef<T>(u64, std::marker::PhantomData<fn() -> T>);
 
 impl<T> SignalRef<T> {
     #[inline]
@@ -284,24 +596,32 @@ impl<T> SignalRef<T> {
     }
 }
 
+/// Reference to an observable value stored in [`Globals`](Globals), created by [`Globals::property`](Globals::property).
+#[derive(Derivative)]
+#[derivative(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derivative(Debug(bound = ""))]
+#[derivative(Clone(bound = ""))]
+#[derivative(Copy(bound = ""))]
+#[derivative(PartialEq(bound = ""))]
+#[derivative(Eq(bound = ""))]
+#[derivative(PartialOrd(bound = ""))]
+#[derivative(Ord(bound = ""))]
+#[derivative(Hash(bound = ""))]
+pub struct PropertyRef<T> {
+    id: u64,
+    /// Emitted with the new value whenever the property is changed.
+    pub on_changed: SignalRef<T>,
+}
+
 trait InternalSignal {
-    fn emit(&mut self, globals: &mut Globals, event: &dyn Any);
-    fn listen(&mut self, listener: &dyn Any) -> signal::ListenerRef;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
     fn detach(&mut self, listener: signal::ListenerRef);
 }
 
 impl<T: 'static> InternalSignal for signal::Signal<T> {
     #[inline]
-    fn emit(&mut self, globals: &mut Globals, event: &dyn Any) {
-        self.emit(globals, event.downcast_ref::<T>().unwrap())
-    }
-
-    fn listen(&mut self, listener: &dyn Any) -> signal::ListenerRef {
-        self.listen_rc(Rc::clone(
-            listener
-                .downcast_ref::<Rc<dyn Fn(&mut Globals, &T)>>()
-                .unwrap(),
-        ))
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
     }
 
     #[inline]
@@ -310,14 +630,320 @@ impl<T: 'static> InternalSignal for signal::Signal<T> {
     }
 }
 
+/// A structural invariant of the component tree which doesn't hold, as reported by [`Globals::validate`](Globals::validate).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Violation {
+    #[error("{parent_type} ({parent:?}) lists a child {child:?} which doesn't exist")]
+    DanglingChild {
+        parent: UntypedComponentRef,
+        parent_type: &'static str,
+        child: UntypedComponentRef,
+    },
+    #[error("{child_type} ({child:?}) is listed as a child of {parent_type} ({parent:?}) but its parent is {actual_parent:?}")]
+    ParentMismatch {
+        child: UntypedComponentRef,
+        child_type: &'static str,
+        parent: UntypedComponentRef,
+        parent_type: &'static str,
+        actual_parent: UntypedComponentRef,
+    },
+    #[error("{node_type} ({node:?}) is orphaned; its parent {parent:?} doesn't exist or doesn't list it as a child")]
+    Orphaned {
+        node: UntypedComponentRef,
+        node_type: &'static str,
+        parent: UntypedComponentRef,
+    },
+    #[error(
+        "{node_type} ({node:?}) has a listener attached to signal {signal} which doesn't exist"
+    )]
+    DeadSignal {
+        node: UntypedComponentRef,
+        node_type: &'static str,
+        signal: u64,
+    },
+    #[error("{node_type} ({node:?}) is taken outside of a call")]
+    Taken {
+        node: UntypedComponentRef,
+        node_type: &'static str,
+    },
+}
+
+/// Error returned by the fallible `try_*` variants of [`Globals`](Globals) methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum CoreError {
+    #[error("component reference {0:?} is invalid (the component was most likely unmounted)")]
+    InvalidReference(UntypedComponentRef),
+    #[error("component reference {0:?} doesn't match the type of the component")]
+    TypeMismatch(UntypedComponentRef),
+    #[error("component {0:?} is already being used (call trace is most likely from within the component)")]
+    ComponentInUse(UntypedComponentRef),
+    #[error("signal {0} is invalid (the signal was most likely removed)")]
+    InvalidSignal(u64),
+    #[error("signal {0} is already being emitted (call trace is most likely from a listener of the signal)")]
+    SignalInUse(u64),
+}
+
+/// Summary of a [`Globals::shutdown`](Globals::shutdown).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TeardownReport {
+    /// IDs of signals still alive after every component was unmounted (excluding those owned by `Globals` itself).
+    pub leaked_signals: Vec<u64>,
+    /// Number of listeners which were scheduled for removal but never detached.
+    pub pending_listener_removals: usize,
+    /// Components which panicked in their [`unmount`](Component::unmount), along with their type names.
+    pub unmount_panics: Vec<(UntypedComponentRef, &'static str)>,
+}
+
+impl TeardownReport {
+    /// Returns `true` if nothing leaked and no component panicked.
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.leaked_signals.is_empty()
+            && self.pending_listener_removals == 0
+            && self.unmount_panics.is_empty()
+    }
+}
+
+struct SignalSlot {
+    /// `None` while the signal is being emitted.
+    signal: Option<Box<dyn InternalSignal>>,
+    /// Listeners detached while the signal was being emitted, to be removed once it's done.
+    pending_removal: Vec<signal::ListenerRef>,
+}
+
+type ProxyCommand = Box<dyn FnOnce(&mut Globals) + Send>;
+
+/// Thread-safe handle used to schedule work on a [`Globals`](Globals) from other threads.
+///
+/// Scheduled commands are queued and only executed when the UI thread calls [`Globals::pump`](Globals::pump).
+#[derive(Clone)]
+pub struct GlobalsProxy {
+    queue: Arc<Mutex<Vec<ProxyCommand>>>,
+}
+
+impl GlobalsProxy {
+    /// Queues a command to be executed on the UI thread.
+    pub fn schedule(&self, command: impl FnOnce(&mut Globals) + Send + 'static) {
+        self.queue
+            .lock()
+            .expect("proxy queue poisoned")
+            .push(Box::new(command));
+    }
+}
+
+/// A component entering or leaving the tree, as emitted by [`Globals::on_component_mounted`](Globals::on_component_mounted)
+/// and [`Globals::on_component_unmounted`](Globals::on_component_unmounted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LifecycleEvent {
+    pub cref: UntypedComponentRef,
+    /// Type name of the component.
+    pub type_name: &'static str,
+}
+
+/// Per-node signals created on demand by [`Globals::on_child_added`](Globals::on_child_added) and [`Globals::on_child_removed`](Globals::on_child_removed).
+#[derive(Debug, Clone, Copy)]
+struct ChildSignals {
+    added: SignalRef<UntypedComponentRef>,
+    removed: SignalRef<UntypedComponentRef>,
+}
+
+/// Outcome of [`Globals::emit_until_handled`](Globals::emit_until_handled).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EmitResult {
+    /// A listener [marked the event handled](Globals::mark_handled); the remaining listeners were skipped.
+    Handled,
+    /// Every listener received the event without consuming it.
+    Unhandled,
+}
+
+/// Details of a panic caught in a component callback, as emitted by [`Globals::on_component_panic`](Globals::on_component_panic).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentPanic {
+    /// The component whose callback panicked.
+    pub cref: UntypedComponentRef,
+    /// Type name of the component.
+    pub type_name: &'static str,
+    /// The panic message, if it was a string.
+    pub message: Option<String>,
+}
+
+/// User accessibility preferences, typically mirrored from the OS settings by the application runner.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AccessibilityPrefs {
+    /// Themes should prefer palettes with boosted contrast.
+    pub high_contrast: bool,
+    /// Animations should be skipped or shortened.
+    pub reduce_motion: bool,
+    /// Text should be rendered larger than usual.
+    pub large_text: bool,
+}
+
+/// How urgently a screen reader should speak an [`Announcement`](Announcement).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Politeness {
+    /// Spoken once the screen reader is idle.
+    Polite,
+    /// Interrupts whatever the screen reader is currently saying.
+    Assertive,
+}
+
+impl Default for Politeness {
+    fn default() -> Self {
+        Politeness::Polite
+    }
+}
+
+/// Text to be spoken by assistive technology, as emitted by [`Globals::on_announce`](Globals::on_announce).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Announcement {
+    pub text: String,
+    pub politeness: Politeness,
+}
+
+/// A node within a [`TreeSnapshot`](TreeSnapshot).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotNode {
+    /// Parent of the node. The root is its own parent.
+    pub parent: UntypedComponentRef,
+    /// Children of the node, in order.
+    pub children: Vec<UntypedComponentRef>,
+    /// Type name of the component.
+    pub type_name: &'static str,
+    /// Number of managed listeners attached to the node.
+    pub listeners: usize,
+}
+
+/// Read-only copy of the UI tree structure, as returned by [`Globals::freeze`](Globals::freeze).
+///
+/// Unlike `Globals`, this is `Send`, so it can be handed to background threads (devtools, accessibility exporters, etc).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeSnapshot {
+    nodes: HashMap<UntypedComponentRef, SnapshotNode>,
+    roots: Vec<UntypedComponentRef>,
+}
+
+impl TreeSnapshot {
+    /// Returns the node behind `cref`, if it existed when the snapshot was taken.
+    #[inline]
+    pub fn get(&self, cref: impl CRef) -> Option<&SnapshotNode> {
+        self.nodes.get(&UntypedComponentRef(cref.id()))
+    }
+
+    /// Returns the root nodes of the tree.
+    #[inline]
+    pub fn roots(&self) -> &[UntypedComponentRef] {
+        &self.roots
+    }
+
+    /// Returns an iterator over all nodes in the snapshot, in no particular order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (UntypedComponentRef, &SnapshotNode)> {
+        self.nodes.iter().map(|(cref, node)| (*cref, node))
+    }
+
+    /// Returns the number of nodes in the snapshot.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the snapshot has no nodes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Formats the subtree rooted at `cref` as an indented outline, one node per line.
+    pub fn dump(&self, cref: impl CRef) -> String {
+        let mut out = String::new();
+        let mut stack = vec![(UntypedComponentRef(cref.id()), 0)];
+        while let Some((current, depth)) = stack.pop() {
+            let node = match self.nodes.get(&current) {
+                Some(node) => node,
+                None => continue,
+            };
+            out.push_str(&format!(
+                "{:indent$}{} #{} (children: {}, listeners: {})\n",
+                "",
+                node.type_name,
+                current.0,
+                node.children.len(),
+                node.listeners,
+                indent = depth * 2
+            ));
+            stack.extend(node.children.iter().rev().map(|child| (*child, depth + 1)));
+        }
+        out
+    }
+}
+
+/// Error returned by [`Globals::persist_to`](Globals::persist_to) and [`Globals::restore_from`](Globals::restore_from).
+#[cfg(feature = "persistence")]
+#[derive(Debug, Error)]
+pub enum PersistError {
+    #[error("failed to access persisted state: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("failed to (de)serialize persisted state: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
 pub struct Globals {
     pub on_theme_changed: SignalRef<()>,
-    map: HashMap<u64, Box<dyn InternalNode>>,
-    signal_map: HashMap<u64, Option<Box<dyn InternalSignal>>>,
-    listener_removal: Vec<signal::ListenerRef>,
-    next_component_id: u64,
-    next_signal_id: u64,
+    /// Emitted when a component's `update` or one of its managed listeners panics.
+    ///
+    /// The panic is caught and the component is restored, so the rest of the UI keeps working.
+    /// The component is also marked as [poisoned](Globals::is_poisoned).
+    pub on_component_panic: SignalRef<ComponentPanic>,
+    /// Emitted with the new value whenever [`can_undo`](commands::UndoStack::can_undo) changes.
+    pub on_can_undo_changed: SignalRef<bool>,
+    /// Emitted with the new value whenever [`can_redo`](commands::UndoStack::can_redo) changes.
+    pub on_can_redo_changed: SignalRef<bool>,
+    /// Emitted with the reference of every node removed from the tree.
+    ///
+    /// Emission happens once the whole unmount operation is complete, so the node (and its subtree) will already be gone.
+    /// Useful for caches, selection models and focus management to drop references to dead components.
+    pub on_unmounted: SignalRef<UntypedComponentRef>,
+    /// Emitted whenever a component is mounted, once its [`mounted`](Component::mounted) hook has run.
+    pub on_component_mounted: SignalRef<LifecycleEvent>,
+    /// Same as [`on_unmounted`](Globals::on_unmounted), but also carrying the type name of the removed component.
+    pub on_component_unmounted: SignalRef<LifecycleEvent>,
+    /// Emitted with the new preferences whenever [`set_accessibility_prefs`](Globals::set_accessibility_prefs) changes them.
+    pub on_accessibility_changed: SignalRef<AccessibilityPrefs>,
+    /// Emitted by [`announce`](Globals::announce). Accessibility backends listen to this and forward it to the platform screen reader.
+    pub on_announce: SignalRef<Announcement>,
+    map: Arena<Box<dyn InternalNode>>,
+    signal_map: Arena<SignalSlot>,
     theme: Box<dyn theme::Theme>,
+    perf: perf::Counters,
+    #[cfg(feature = "profiler")]
+    profile: perf::ProfileReport,
+    proxy_queue: Arc<Mutex<Vec<ProxyCommand>>>,
+    properties: Arena<Rc<dyn Any>>,
+    prop_signals: HashMap<&'static str, SignalRef<UntypedComponentRef>>,
+    undo_stack: commands::UndoStack,
+    unmounted: Vec<LifecycleEvent>,
+    update_depth: usize,
+    update_queue: Vec<(UntypedComponentRef, Repaint, Propagate)>,
+    accessibility: AccessibilityPrefs,
+    names: HashMap<String, UntypedComponentRef>,
+    node_names: HashMap<UntypedComponentRef, Vec<String>>,
+    poisoned: HashSet<UntypedComponentRef>,
+    #[cfg(debug_assertions)]
+    hooks: Vec<(&'static str, usize)>,
+    internal_signals: HashSet<u64>,
+    dirty: HashSet<UntypedComponentRef>,
+    pool: HashMap<TypeId, Vec<UntypedComponentRef>>,
+    pooled: HashMap<UntypedComponentRef, TypeId>,
+    scheduled: BTreeMap<Phase, Vec<UntypedComponentRef>>,
+    frozen: HashSet<UntypedComponentRef>,
+    child_signals: HashMap<UntypedComponentRef, ChildSignals>,
+    removed_children: Vec<(UntypedComponentRef, UntypedComponentRef)>,
+    derived: HashMap<u64, Vec<u64>>,
+    forwarders: HashMap<u64, ListenerPair>,
+    listening: HashMap<u64, HashSet<UntypedComponentRef>>,
+    emit_depth: usize,
+    deferred_emits: Vec<Box<dyn FnOnce(&mut Globals)>>,
+    handled: Vec<bool>,
 }
 
 impl Globals {
@@ -325,33 +951,89 @@ impl Globals {
     pub fn new<T: ComponentFactory>(theme: impl theme::Theme + 'static) -> (Self, ComponentRef<T>) {
         let mut globals = Globals {
             on_theme_changed: SignalRef::null(),
+            on_component_panic: SignalRef::null(),
+            on_can_undo_changed: SignalRef::null(),
+            on_can_redo_changed: SignalRef::null(),
+            on_unmounted: SignalRef::null(),
+            on_component_mounted: SignalRef::null(),
+            on_component_unmounted: SignalRef::null(),
+            on_accessibility_changed: SignalRef::null(),
+            on_announce: SignalRef::null(),
 
             map: Default::default(),
             signal_map: Default::default(),
-            listener_removal: Default::default(),
-            next_component_id: 0,
-            next_signal_id: 0,
             theme: Box::new(theme),
+            perf: Default::default(),
+            #[cfg(feature = "profiler")]
+            profile: Default::default(),
+            proxy_queue: Default::default(),
+            properties: Default::default(),
+            prop_signals: Default::default(),
+            undo_stack: Default::default(),
+            unmounted: Vec::new(),
+            update_depth: 0,
+            update_queue: Vec::new(),
+            accessibility: Default::default(),
+            names: Default::default(),
+            node_names: Default::default(),
+            poisoned: Default::default(),
+            #[cfg(debug_assertions)]
+            hooks: Vec::new(),
+            internal_signals: Default::default(),
+            dirty: Default::default(),
+            pool: Default::default(),
+            pooled: Default::default(),
+            scheduled: Default::default(),
+            frozen: Default::default(),
+            child_signals: Default::default(),
+            removed_children: Vec::new(),
+            derived: Default::default(),
+            forwarders: Default::default(),
+            listening: Default::default(),
+            emit_depth: 0,
+            deferred_emits: Vec::new(),
+            handled: Vec::new(),
         };
 
-        globals.on_theme_changed = globals.signal();
+        globals.on_theme_changed = globals.internal_signal();
+        globals.on_component_panic = globals.internal_signal();
+        globals.on_can_undo_changed = globals.internal_signal();
+        globals.on_can_redo_changed = globals.internal_signal();
+        globals.on_unmounted = globals.internal_signal();
+        globals.on_component_mounted = globals.internal_signal();
+        globals.on_component_unmounted = globals.internal_signal();
+        globals.on_accessibility_changed = globals.internal_signal();
+        globals.on_announce = globals.internal_signal();
 
-        let root = ComponentRef(globals.next_component_id, Default::default());
-        globals.next_component_id += 1;
-        globals.map.insert(
-            root.0,
-            Box::new(ComponentNode::<T> {
-                parent: UntypedComponentRef(root.0),
-                children: Vec::new(),
-                component: None,
-                listeners: Vec::new(),
-                cmds: Default::default(),
-            }),
-        );
+        let root = globals.new_root();
+        (globals, root)
+    }
 
-        globals.node_mut(root).component = Some(T::new(&mut globals, root));
+    /// Creates an additional root component, independent of the existing tree(s) but sharing the theme, signals and resources.
+    ///
+    /// This is intended for applications with several windows or surfaces.
+    pub fn new_root<T: ComponentFactory>(&mut self) -> ComponentRef<T> {
+        let node = ComponentNode::<T>::new(UntypedComponentRef(std::u64::MAX));
+        let root = ComponentRef(self.map.insert(Box::new(node)), Default::default());
 
-        (globals, root)
+        self.node_mut(root).parent = UntypedComponentRef(root.0);
+        self.perf.mounts += 1;
+        self.dirty.insert(UntypedComponentRef(root.0));
+        self.node_mut(root).component = Some(T::new(self, root));
+        self.finish_mount(UntypedComponentRef(root.0));
+
+        root
+    }
+
+    /// Returns every root component, in no particular order.
+    ///
+    /// This includes the roots of detached subtrees, such as [pooled](Globals::detach_to_pool) ones.
+    pub fn roots(&self) -> Vec<UntypedComponentRef> {
+        self.map
+            .ids()
+            .filter(|&id| self.map.get(id).unwrap().parent().0 == id)
+            .map(UntypedComponentRef)
+            .collect()
     }
 
     /// Immutably retrieves the `Component` behind a reference.
@@ -389,10 +1071,38 @@ impl Globals {
         self.try_node_mut(cref)?.component.as_mut()
     }
 
+    /// Temporarily takes the component behind `cref` out of the tree, so that it can be used alongside `&mut Globals`.
+    ///
+    /// While `f` runs, the component is "in use": retrieving it through `globals` fails (e.g. [`try_get`](Globals::try_get) returns `None`)
+    /// and [updates](Globals::update) to it are queued until it is back in place. Panics within `f` are propagated once the component has been restored.
+    pub fn with<T: Component, R>(
+        &mut self,
+        cref: ComponentRef<T>,
+        f: impl FnOnce(&mut T, &mut Globals) -> R,
+    ) -> R {
+        let mut component = self
+            .node_mut(cref)
+            .component
+            .take()
+            .expect("a reference to the component is already being used");
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&mut component, self)));
+        self.node_mut(cref).component = Some(component);
+
+        if self.update_depth == 0 {
+            self.flush_update_queue();
+        }
+
+        match result {
+            Ok(result) => result,
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    }
+
     /// Immutably retrieves the `ComponentNode` behind a reference.
     pub fn node<T: Component>(&self, cref: ComponentRef<T>) -> &ComponentNode<T> {
         self.map
-            .get(&cref.0)
+            .get(cref.0)
             .expect("invalid reference")
             .as_any()
             .downcast_ref::<ComponentNode<T>>()
@@ -402,7 +1112,7 @@ impl Globals {
     /// Mutably retrieves the `ComponentNode` behind a reference.
     pub fn node_mut<T: Component>(&mut self, cref: ComponentRef<T>) -> &mut ComponentNode<T> {
         self.map
-            .get_mut(&cref.0)
+            .get_mut(cref.0)
             .expect("invalid reference")
             .as_any_mut()
             .downcast_mut::<ComponentNode<T>>()
@@ -412,7 +1122,7 @@ impl Globals {
     /// Attempts to immutably retrieve the `Component` behind a reference, returning `None` if it failed.
     pub fn try_node<T: Component>(&self, cref: ComponentRef<T>) -> Option<&ComponentNode<T>> {
         self.map
-            .get(&cref.0)?
+            .get(cref.0)?
             .as_any()
             .downcast_ref::<ComponentNode<T>>()
     }
@@ -423,7 +1133,7 @@ impl Globals {
         cref: ComponentRef<T>,
     ) -> Option<&mut ComponentNode<T>> {
         self.map
-            .get_mut(&cref.0)?
+            .get_mut(cref.0)?
             .as_any_mut()
             .downcast_mut::<ComponentNode<T>>()
     }
@@ -440,10 +1150,53 @@ impl Globals {
         self.untyped_internal_node_mut(&cref).as_node_mut()
     }
 
-    /// Returns `true` if the provided reference is valid (hasn't been unmounted), otherwise `false`.
+    /// Walks the subtree rooted at `cref` (inclusive) in pre-order, i.e. parents before their children.
+    pub fn visit(&self, cref: impl CRef, mut visitor: impl FnMut(UntypedComponentRef, &dyn Node)) {
+        let root = UntypedComponentRef(cref.id());
+        visitor(root, self.untyped_node(root));
+        for child in self.descendants(root) {
+            visitor(child, self.untyped_node(child));
+        }
+    }
+
+    /// Walks the subtree rooted at `cref` (inclusive) in post-order, i.e. children before their parents.
+    pub fn visit_post_order(
+        &self,
+        cref: impl CRef,
+        mut visitor: impl FnMut(UntypedComponentRef, &dyn Node),
+    ) {
+        let mut stack = vec![(UntypedComponentRef(cref.id()), false)];
+        while let Some((current, expanded)) = stack.pop() {
+            let node = self.untyped_node(current);
+            if expanded {
+                visitor(current, node);
+            } else {
+                stack.push((current, true));
+                stack.extend(node.children().iter().rev().map(|child| (*child, false)));
+            }
+        }
+    }
+
+    /// Returns a pre-order iterator over every descendant of `cref`, excluding `cref` itself.
+    pub fn descendants(&self, cref: impl CRef) -> impl Iterator<Item = UntypedComponentRef> + '_ {
+        let mut stack: Vec<_> = self
+            .untyped_node(UntypedComponentRef(cref.id()))
+            .children()
+            .iter()
+            .rev()
+            .copied()
+            .collect();
+        std::iter::from_fn(move || {
+            let current = stack.pop()?;
+            stack.extend(self.untyped_node(current).children().iter().rev());
+            Some(current)
+        })
+    }
+
+    /// Returns `true` if the provided reference is valid (hasn't been unmounted), otherwise `false`.
     #[inline]
     pub fn is_valid(&self, cref: impl CRef) -> bool {
-        self.map.contains_key(&cref.id())
+        self.map.contains(cref.id())
     }
 
     /// Returns `true` if the `Component` isn't in the stack trace and is available, otherwise `false`.
@@ -452,7 +1205,7 @@ impl Globals {
     #[inline]
     pub fn is_available(&self, cref: impl CRef) -> bool {
         self.map
-            .get(&cref.id())
+            .get(cref.id())
             .and_then(|x| Some(!x.is_taken()))
             .unwrap_or(false)
     }
@@ -463,195 +1216,2912 @@ impl Globals {
     #[inline]
     pub fn is_of_type<T: Component>(&self, cref: ComponentRef<T>) -> bool {
         self.map
-            .get(&cref.id())
-            .and_then(|x| Some(x.type_id() == std::any::TypeId::of::<T>()))
+            .get(cref.id())
+            .map(|x| x.as_any().is::<ComponentNode<T>>())
             .unwrap_or(false)
     }
 
+    /// Turns a weak reference back into a regular one, or returns `None` if the component has been unmounted.
+    #[inline]
+    pub fn upgrade<T: Component>(&self, weak: WeakComponentRef<T>) -> Option<ComponentRef<T>> {
+        let cref = ComponentRef(weak.0, Default::default());
+        if self.is_of_type(cref) {
+            Some(cref)
+        } else {
+            None
+        }
+    }
+
+    /// Registers `cref` under a unique name so that it can be found from anywhere with [`lookup_name`](Globals::lookup_name).
+    ///
+    /// Any component previously registered under `name` is replaced. The registration is removed when `cref` is unmounted.
+    pub fn register_name(&mut self, cref: impl CRef, name: impl Into<String>) {
+        assert!(self.map.contains(cref.id()), "invalid reference");
+        let cref = UntypedComponentRef(cref.id());
+        let name = name.into();
+        self.unregister_name(&name);
+        self.node_names.entry(cref).or_default().push(name.clone());
+        self.names.insert(name, cref);
+    }
+
+    /// Removes a name registration, returning the component it referred to.
+    pub fn unregister_name(&mut self, name: &str) -> Option<UntypedComponentRef> {
+        let cref = self.names.remove(name)?;
+        if let Some(names) = self.node_names.get_mut(&cref) {
+            names.retain(|x| x != name);
+            if names.is_empty() {
+                self.node_names.remove(&cref);
+            }
+        }
+        Some(cref)
+    }
+
+    /// Returns the component registered under `name`, or `None` if there isn't one or it isn't of type `T`.
+    pub fn lookup_name<T: Component>(&self, name: &str) -> Option<ComponentRef<T>> {
+        let cref = self.names.get(name)?.typed_unchecked();
+        if self.is_of_type(cref) {
+            Some(cref)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to every mounted component of type `T`, in no particular order.
+    pub fn find_all<T: Component>(&self) -> Vec<ComponentRef<T>> {
+        self.map
+            .ids()
+            .filter(|id| self.map.get(*id).unwrap().as_any().is::<ComponentNode<T>>())
+            .map(|id| ComponentRef(id, Default::default()))
+            .collect()
+    }
+
+    /// Returns the first component of type `T` found in a pre-order walk of the subtree rooted at `cref` (inclusive).
+    pub fn find_first<T: Component>(&self, cref: impl CRef) -> Option<ComponentRef<T>> {
+        let root = UntypedComponentRef(cref.id());
+        std::iter::once(root)
+            .chain(self.descendants(root))
+            .map(|x| x.typed_unchecked::<T>())
+            .find(|x| self.is_of_type(*x))
+    }
+
+    /// Returns the type name of the component behind a reference, or `None` if the reference is invalid.
+    ///
+    /// This is intended for diagnostics only; the exact name isn't guaranteed to be stable.
+    #[inline]
+    pub fn type_name_of(&self, cref: impl CRef) -> Option<&'static str> {
+        self.map.get(cref.id()).map(|x| x.type_name())
+    }
+
     /// Unmounts and removes a component node (and it's children).
     ///
     /// If you require access to parent or children from within [component unmount](Component::unmount), consider using [`late_unmount`](Globals::late_unmount) instead.
     #[inline]
     pub fn unmount(&mut self, cref: impl CRef) {
-        self.unmount_single(&cref);
-        self.unmount_children(&cref, false);
+        let parent = self.untyped_internal_node(&cref).parent();
+        self.unmount_impl(&cref, false);
+        self.debug_validate(parent.0);
+        self.flush_unmounted();
+    }
+
+    /// Swaps the component behind `cref` for a newly constructed `New`, keeping the node's position, children and properties.
+    ///
+    /// The old component is [unmounted](Component::unmount) and its managed listeners are removed, but its children are left untouched.
+    /// Lifecycle signals report this as the old component unmounting followed by the new one mounting.
+    /// `cref` is invalid afterwards; use the returned reference instead (which has the same ID).
+    pub fn replace_component<Old: Component, New: ComponentFactory>(
+        &mut self,
+        cref: ComponentRef<Old>,
+    ) -> ComponentRef<New> {
+        let mut component = self
+            .node_mut(cref)
+            .component
+            .take()
+            .expect("a reference to the component is already being used");
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| component.unmount(self)));
+        if let Err(payload) = result {
+            self.report_panic(
+                UntypedComponentRef(cref.0),
+                std::any::type_name::<Old>(),
+                payload,
+            );
+        }
+
+        let old = self.node_mut(cref);
+        let mut node = ComponentNode::<New>::new(old.parent);
+        node.children = std::mem::take(&mut old.children);
+        node.props = std::mem::take(&mut old.props);
+        node.contexts = std::mem::take(&mut old.contexts);
+        node.meta = std::mem::take(&mut old.meta);
+        node.cmds.repaint();
+        self.dirty.insert(UntypedComponentRef(cref.0));
+        self.poisoned.remove(&UntypedComponentRef(cref.0));
+
+        let mut old = std::mem::replace(self.map.get_mut(cref.0).unwrap(), Box::new(node));
+        self.unindex_listeners(UntypedComponentRef(cref.0), &old.listener_signals());
+        old.detach_listeners(self);
+        self.remove_signals(old.owned_signals());
+        self.child_signals.remove(&UntypedComponentRef(cref.0));
+        self.perf.unmounts += 1;
+        #[cfg(feature = "profiler")]
+        self.profile
+            .by_instance
+            .remove(&UntypedComponentRef(cref.0));
+        self.unmounted.push(LifecycleEvent {
+            cref: UntypedComponentRef(cref.0),
+            type_name: std::any::type_name::<Old>(),
+        });
+
+        let new = ComponentRef(cref.0, Default::default());
+        self.perf.mounts += 1;
+        self.node_mut(new).component = Some(New::new(self, new));
+        self.debug_validate(new.0);
+        self.flush_unmounted();
+        self.finish_mount(UntypedComponentRef(new.0));
+
+        new
+    }
+
+    /// Same as [`unmount`](Globals::unmount), but returns an error if `cref` is invalid or currently in use.
+    pub fn try_unmount(&mut self, cref: impl CRef) -> Result<(), CoreError> {
+        self.check_available(&cref)?;
+        self.unmount(cref);
+        Ok(())
     }
 
     /// Same as [`unmount`](Globals::unmount), however children are unmounted *before* the component.
     #[inline]
     pub fn reverse_unmount(&mut self, cref: impl CRef) {
-        self.unmount_children(&cref, true);
-        self.unmount_single(&cref);
+        let parent = self.untyped_internal_node(&cref).parent();
+        self.unmount_impl(&cref, true);
+        self.debug_validate(parent.0);
+        self.flush_unmounted();
     }
 
     /// Same as [`unmount`](Globals::unmount), however everything is erased after all the `unmount` callbacks have been made.
     ///
     /// This gives the `unmount` callbacks most flexibility in terms of the existence of parent/children but is the slowest unmount method (two iterations over local UI tree instead of one).
     pub fn late_unmount(&mut self, cref: impl CRef) {
+        let parent = self.untyped_internal_node(&cref).parent();
         let mut v = Vec::new();
         self.late_unmount_impl(cref, &mut v);
         for id in v {
-            if let Some(mut node) = self.map.remove(&id) {
-                node.detach_listeners(self);
+            self.remove_node(id);
+        }
+        self.debug_validate(parent.0);
+        self.flush_unmounted();
+    }
+
+    /// Deterministically unmounts every component and reports anything left behind.
+    ///
+    /// Each root is torn down depth-first, children before their parent, and lifecycle events are emitted as usual,
+    /// so surviving ancestors still observe their descendants being unmounted.
+    /// Unlike dropping `Globals`, a component panicking in `unmount` doesn't abort the teardown; it is recorded in the report instead.
+    pub fn shutdown(mut self) -> TeardownReport {
+        let mut report = TeardownReport::default();
+
+        let roots: Vec<_> = self
+            .map
+            .ids()
+            .filter(|&id| self.map.get(id).unwrap().parent().0 == id)
+            .collect();
+        for id in roots {
+            if self.map.contains(id) {
+                self.shutdown_impl(UntypedComponentRef(id), &mut report);
+            }
+        }
+
+        for id in self.signal_map.ids() {
+            report.pending_listener_removals +=
+                self.signal_map.get(id).unwrap().pending_removal.len();
+            if !self.internal_signals.contains(&id) {
+                report.leaked_signals.push(id);
+            }
+        }
+
+        report
+    }
+
+    /// Writes the [saved state](Component::save_state) of every component to a JSON file.
+    ///
+    /// Components are identified by their position in the tree and their type, so state is only restored into a tree of the same shape.
+    #[cfg(feature = "persistence")]
+    pub fn persist_to(&self, path: impl AsRef<std::path::Path>) -> Result<(), PersistError> {
+        let mut states = serde_json::Map::new();
+        for (key, id) in self.persistence_keys() {
+            if let Some(state) = self.map.get(id).unwrap().save_state() {
+                states.insert(key, state);
+            }
+        }
+
+        std::fs::write(path, serde_json::to_vec_pretty(&states)?)?;
+        Ok(())
+    }
+
+    /// Restores component state previously written by [`persist_to`](Globals::persist_to).
+    ///
+    /// Components without an entry in the file (or whose position/type changed) are left untouched.
+    #[cfg(feature = "persistence")]
+    pub fn restore_from(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), PersistError> {
+        let mut states: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_slice(&std::fs::read(path)?)?;
+        for (key, id) in self.persistence_keys() {
+            if let Some(state) = states.remove(&key) {
+                self.map.get_mut(id).unwrap().restore_state(state);
             }
         }
+
+        Ok(())
+    }
+
+    /// Checks the structural invariants of the entire component tree, returning every violation found.
+    ///
+    /// This should be called outside of any update or listener, otherwise the components in the call trace will be reported as taken.
+    ///
+    /// When debug assertions are enabled, the invariants local to a node are also checked whenever it is mounted or unmounted.
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        for id in self.map.ids() {
+            self.validate_node(id, true, &mut violations);
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Same as [`validate`](Globals::validate), but panics with a description of every violation found.
+    ///
+    /// Handy as an assertion after each step of a test or fuzzing run.
+    pub fn check_invariants(&self) {
+        if let Err(violations) = self.validate() {
+            panic_on_violations(&violations);
+        }
     }
 
     /// Creates a new component as a child of an existing component.
+    #[inline]
     pub fn child<T: ComponentFactory>(&mut self, pcref: impl CRef) -> ComponentRef<T> {
-        let cref = ComponentRef(self.next_component_id, Default::default());
-        self.next_component_id += 1;
+        self.mount_child(pcref, None, T::new)
+    }
 
-        self.untyped_internal_node_mut(&pcref)
-            .push_child(UntypedComponentRef(cref.0));
-        self.map.insert(
-            cref.0,
-            Box::new(ComponentNode::<T> {
-                parent: UntypedComponentRef(pcref.id()),
-                children: Vec::new(),
-                component: None,
-                listeners: Vec::new(),
-                cmds: Default::default(),
-            }),
-        );
+    /// Creates a new component as a child of an existing component, inserted at `index` in the parent's children.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the number of children.
+    #[inline]
+    pub fn child_at<T: ComponentFactory>(
+        &mut self,
+        pcref: impl CRef,
+        index: usize,
+    ) -> ComponentRef<T> {
+        self.mount_child(pcref, Some(index), T::new)
+    }
 
-        self.node_mut(cref).component = Some(T::new(self, cref));
+    /// Creates a new component as a sibling immediately before `sibling`.
+    ///
+    /// # Panics
+    /// Panics if `sibling` is the root.
+    pub fn insert_before<T: ComponentFactory>(&mut self, sibling: impl CRef) -> ComponentRef<T> {
+        let (parent, index) = self.sibling_position(&sibling);
+        self.mount_child(parent, Some(index), T::new)
+    }
 
-        cref
+    /// Creates a new component as a sibling immediately after `sibling`.
+    ///
+    /// # Panics
+    /// Panics if `sibling` is the root.
+    pub fn insert_after<T: ComponentFactory>(&mut self, sibling: impl CRef) -> ComponentRef<T> {
+        let (parent, index) = self.sibling_position(&sibling);
+        self.mount_child(parent, Some(index + 1), T::new)
     }
 
-    /// Invokes an update for a specified component, optionally recursively propagating to children and scheduling a repaint.
-    pub fn update(&mut self, cref: impl CRef, repaint: Repaint, propagate: Propagate) {
-        let mut component = self.untyped_internal_node_mut(&cref).take();
-        component.update(self);
-        self.untyped_internal_node_mut(&cref).replace(component);
+    /// Creates a new component as a child of an existing component, constructing it with initial properties.
+    #[inline]
+    pub fn child_with<T: ComponentFactoryWith<P>, P>(
+        &mut self,
+        pcref: impl CRef,
+        props: P,
+    ) -> ComponentRef<T> {
+        self.mount_child(pcref, None, move |globals, cref| {
+            T::new_with(globals, cref, props)
+        })
+    }
+
+    /// Creates many children of one type in a single pass, one for each set of properties yielded by `iter`.
+    ///
+    /// This is considerably faster than calling [`child_with`](Globals::child_with) in a loop, as the children are appended to the parent all at once.
+    /// As a consequence, the new children won't find themselves in the parent's children while they're being constructed.
+    pub fn children_from_iter<T: ComponentFactoryWith<P>, P, I: IntoIterator<Item = P>>(
+        &mut self,
+        pcref: impl CRef,
+        iter: I,
+    ) -> Vec<ComponentRef<T>> {
+        let parent = UntypedComponentRef(pcref.id());
+        assert!(self.map.contains(parent.0), "invalid reference");
 
-        let node = self.untyped_internal_node_mut(&cref);
+        let iter = iter.into_iter();
+        let mut crefs = Vec::with_capacity(iter.size_hint().0);
+        let mut untyped = Vec::with_capacity(iter.size_hint().0);
+        for props in iter {
+            let node = ComponentNode::<T>::new(parent);
+            let cref = ComponentRef(self.map.insert(Box::new(node)), Default::default());
 
-        if Repaint::Yes == repaint {
-            node.repaint();
+            self.perf.mounts += 1;
+            self.dirty.insert(UntypedComponentRef(cref.0));
+            self.node_mut(cref).component = Some(T::new_with(self, cref, props));
+
+            crefs.push(cref);
+            untyped.push(UntypedComponentRef(cref.0));
         }
 
-        if Propagate::Yes == propagate {
-            for child in node.children().to_vec() {
-                self.update(child, repaint, propagate);
+        self.untyped_internal_node_mut(&parent)
+            .extend_children(&untyped);
+        self.debug_validate(parent.0);
+
+        for cref in &crefs {
+            self.finish_mount(UntypedComponentRef(cref.0));
+            self.emit_child_changed(parent, UntypedComponentRef(cref.0), true);
+        }
+
+        crefs
+    }
+
+    /// Moves the child at `from` so that it ends up at `to` in the parent's children, shifting the children in between.
+    ///
+    /// # Panics
+    /// Panics if either index is out of bounds.
+    pub fn move_child(&mut self, pcref: impl CRef, from: usize, to: usize) {
+        let children = self.untyped_internal_node_mut(&pcref).children_mut();
+        let child = children.remove(from);
+        children.insert(to, child);
+    }
+
+    /// Moves `cref` (along with its subtree) to the end of `new_parent`'s children.
+    ///
+    /// Component state, listeners and node properties are all kept.
+    ///
+    /// # Panics
+    /// Panics if `cref` is the root, or if `new_parent` is `cref` itself or one of its descendants.
+    pub fn reparent(&mut self, cref: impl CRef, new_parent: impl CRef) {
+        let child = UntypedComponentRef(cref.id());
+        assert!(
+            self.untyped_internal_node(&cref).parent() != child,
+            "cannot reparent the root"
+        );
+
+        let mut ancestor = UntypedComponentRef(new_parent.id());
+        loop {
+            assert!(
+                ancestor != child,
+                "cannot reparent a component into its own subtree"
+            );
+            let parent = self.untyped_internal_node(&ancestor).parent();
+            if parent == ancestor {
+                break;
             }
+            ancestor = parent;
         }
+
+        self.detach_impl(child);
+        self.attach_impl(child, UntypedComponentRef(new_parent.id()));
     }
 
-    /// Returns a new painter from the current theme.
-    #[inline]
-    pub fn painter<T: Component>(&self, p: &'static str) -> theme::Painter<T> {
-        theme::get_painter(self.theme.as_ref(), p)
+    /// Lifts `cref` (along with its subtree) out of the tree, for [`attach`](Globals::attach) to put back elsewhere.
+    ///
+    /// No [`unmount`](Component::unmount) is run; state, listeners and properties are all kept.
+    ///
+    /// # Panics
+    /// Panics if `cref` is a root.
+    pub fn detach(&mut self, cref: impl CRef) -> DetachedSubtree {
+        let cref = UntypedComponentRef(cref.id());
+        self.detach_impl(cref);
+        DetachedSubtree(cref)
     }
 
-    /// Changes the current theme.
+    /// Attaches a [detached](Globals::detach) subtree to the end of `parent`'s children, returning its root.
     ///
-    /// Components will only update their painters if they correctly handle `on_theme_changed`.
-    pub fn set_theme(&mut self, theme: impl theme::Theme + 'static) {
-        self.theme = Box::new(theme);
-        self.emit(self.on_theme_changed, &());
+    /// # Panics
+    /// Panics if `parent` is part of the detached subtree.
+    pub fn attach(&mut self, subtree: DetachedSubtree, parent: impl CRef) -> UntypedComponentRef {
+        let parent = UntypedComponentRef(parent.id());
+        assert!(
+            self.root_of(parent) != subtree.0,
+            "cannot attach a subtree to itself"
+        );
+        self.attach_impl(subtree.0, parent);
+        subtree.0
     }
 
-    /// Creates a new signal.
-    pub fn signal<T: 'static>(&mut self) -> SignalRef<T> {
-        let sref = SignalRef(self.next_signal_id, Default::default());
-        self.next_signal_id += 1;
-        self.signal_map
-            .insert(sref.0, Some(Box::new(signal::Signal::<T>::new())));
-        sref
+    /// Lifts `cref` (along with its subtree) out of the tree and keeps it alive in a pool, for [`attach_from_pool`](Globals::attach_from_pool) to reuse.
+    ///
+    /// No [`unmount`](Component::unmount) is run; state, listeners and properties are all kept.
+    /// This makes recycling subtrees (e.g. rows of a virtualized list) cheaper than unmounting and constructing them again.
+    ///
+    /// # Panics
+    /// Panics if `cref` is the root.
+    pub fn detach_to_pool<T: Component>(&mut self, cref: ComponentRef<T>) {
+        self.detach_impl(UntypedComponentRef(cref.0));
+        self.pool
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .push(UntypedComponentRef(cref.0));
+        self.pooled
+            .insert(UntypedComponentRef(cref.0), TypeId::of::<T>());
     }
 
-    /// Emits an event for a signal.
-    pub fn emit<T: 'static>(&mut self, sref: SignalRef<T>, event: &T) {
-        if let Some(mut signal) = self.signal_map.get_mut(&sref.0).and_then(|x| x.take()) {
-            signal.emit(self, event);
-            for listener in std::mem::take(&mut self.listener_removal) {
-                signal.detach(listener);
-            }
-            *self.signal_map.get_mut(&sref.0).unwrap() = Some(signal);
+    /// Takes a pooled component of type `T` and attaches it to the end of `new_parent`'s children.
+    ///
+    /// Returns `None` if there are no pooled components of type `T`, in which case a new one should be created.
+    pub fn attach_from_pool<T: Component>(
+        &mut self,
+        new_parent: impl CRef,
+    ) -> Option<ComponentRef<T>> {
+        let cref = self.pool.get_mut(&TypeId::of::<T>())?.pop()?;
+        self.pooled.remove(&cref);
+        self.attach_impl(cref, UntypedComponentRef(new_parent.id()));
+        Some(cref.typed_unchecked())
+    }
+
+    /// Returns the number of pooled components of type `T`.
+    #[inline]
+    pub fn pooled_count<T: Component>(&self) -> usize {
+        self.pool.get(&TypeId::of::<T>()).map_or(0, Vec::len)
+    }
+
+    /// Unmounts every pooled component of type `T`.
+    pub fn clear_pool<T: Component>(&mut self) {
+        for cref in self.pool.remove(&TypeId::of::<T>()).unwrap_or_default() {
+            self.unmount(cref);
         }
     }
 
-    /// Adds a managed listener to a signal.
+    /// Replaces the order of a parent's children.
     ///
-    /// "Managed" implies that the listener will be removed when `cref` is unmounted.
-    pub fn listen<T: 'static, C: Component>(
-        &mut self,
-        sref: SignalRef<T>,
-        cref: ComponentRef<C>,
-        listener: impl Fn(&mut Globals, &T) + 'static,
-    ) {
-        let listener: Rc<dyn Fn(&mut Globals, &T)> = Rc::new(listener);
-        let listener = self
-            .signal_map
-            .get_mut(&sref.0)
-            .expect("invalid signal ref")
-            .as_mut()
-            .expect("signal already borrowed (call trace is mostly likely from a listener for this signal)")
-            .listen(&listener);
-        self.node_mut(cref).listeners.push(ListenerPair {
-            listener,
-            signal: sref.0,
-        })
+    /// # Panics
+    /// Panics if `order` isn't a permutation of the current children.
+    pub fn set_child_order(&mut self, pcref: impl CRef, order: Vec<UntypedComponentRef>) {
+        let children = self.untyped_internal_node_mut(&pcref).children_mut();
+        let mut current = children.clone();
+        let mut sorted = order.clone();
+        current.sort();
+        sorted.sort();
+        assert!(
+            current == sorted,
+            "child order must be a permutation of the existing children"
+        );
+        *children = order;
     }
-}
 
-impl Globals {
-    fn late_unmount_impl(&mut self, cref: impl CRef, v: &mut Vec<u64>) {
-        v.push(cref.id());
+    /// Invokes an update for a specified component, optionally recursively propagating to children and scheduling a repaint.
+    ///
+    /// If the component is already being updated (e.g. it updates itself, or a listener fired from its `update` does), the request
+    /// is queued and carried out once the outermost update completes.
+    pub fn update(&mut self, cref: impl CRef, repaint: Repaint, propagate: Propagate) {
+        if self.untyped_internal_node(&cref).is_taken() {
+            self.update_queue
+                .push((UntypedComponentRef(cref.id()), repaint, propagate));
+            return;
+        }
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "update",
+            component = self.untyped_internal_node(&cref).type_name(),
+            id = cref.id()
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        self.perf.updates += 1;
+        #[cfg(feature = "profiler")]
+        let start = std::time::Instant::now();
+
+        let allocations = self.start_hot_path();
+        self.update_depth += 1;
+        #[cfg(debug_assertions)]
+        self.enter_hook(&cref);
         let mut component = self.untyped_internal_node_mut(&cref).take();
-        component.unmount(self);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            component.update(self);
+        }));
         self.untyped_internal_node_mut(&cref).replace(component);
+        #[cfg(debug_assertions)]
+        self.hooks.pop();
 
-        for child in self.untyped_internal_node(&cref).children().to_vec() {
-            self.late_unmount_impl(child, v);
+        #[cfg(feature = "profiler")]
+        {
+            let type_name = self.untyped_internal_node(&cref).type_name();
+            self.profile.record(
+                UntypedComponentRef(cref.id()),
+                type_name,
+                start.elapsed(),
+                |x| &mut x.update,
+            );
+        }
+
+        if let Err(payload) = result {
+            let type_name = self.untyped_internal_node(&cref).type_name();
+            self.report_panic(UntypedComponentRef(cref.id()), type_name, payload);
+        }
+
+        if Repaint::Yes == repaint {
+            self.schedule_repaint(UntypedComponentRef(cref.id()));
+        }
+
+        if Propagate::Yes == propagate {
+            for child in self.untyped_internal_node(&cref).children().to_vec() {
+                if !self.frozen.contains(&child) {
+                    self.update(child, repaint, propagate);
+                }
+            }
+        }
+
+        self.update_depth -= 1;
+        if self.update_depth == 0 {
+            self.flush_update_queue();
         }
+        self.end_hot_path(allocations);
     }
 
-    fn unmount_single(&mut self, cref: &impl CRef) {
-        let mut component = self.untyped_internal_node_mut(cref).take();
-        component.unmount(self);
-        self.untyped_internal_node_mut(cref).replace(component);
-        if let Some(mut node) = self.map.remove(&cref.id()) {
-            node.detach_listeners(self);
+    /// Freezes or unfreezes the subtree rooted at `cref`.
+    ///
+    /// Frozen subtrees are skipped when an [`update`](Globals::update) propagates into them, never have repaints scheduled,
+    /// and are left out of [`render`](Globals::render), so this suits content which is hidden, such as background tabs or collapsed panels.
+    ///
+    /// Updates missed while frozen aren't replayed; update the subtree after unfreezing it to catch up.
+    pub fn set_frozen(&mut self, cref: impl CRef, frozen: bool) {
+        let cref = UntypedComponentRef(cref.id());
+        if frozen {
+            self.frozen.insert(cref);
+        } else {
+            self.frozen.remove(&cref);
         }
     }
 
-    fn unmount_children(&mut self, cref: &impl CRef, reverse: bool) {
-        if !self.map.contains_key(&cref.id()) {
-            return;
+    /// Returns `true` if `cref` or any of its ancestors is [frozen](Globals::set_frozen).
+    pub fn is_frozen(&self, cref: impl CRef) -> bool {
+        if self.frozen.is_empty() {
+            return false;
         }
 
-        for child in self.untyped_internal_node(cref).children().to_vec() {
-            if self.map.contains_key(&child.0) {
-                if reverse {
-                    self.reverse_unmount(child);
-                } else {
-                    self.unmount(child);
+        let mut cref = UntypedComponentRef(cref.id());
+        loop {
+            if self.frozen.contains(&cref) {
+                return true;
+            }
+            let parent = self.untyped_internal_node(&cref).parent();
+            if parent == cref {
+                return false;
+            }
+            cref = parent;
+        }
+    }
+
+    /// Schedules `cref` to have [`run_phase`](Component::run_phase) invoked with `phase` during the next [`run_frame`](Globals::run_frame).
+    ///
+    /// Scheduling a component for the same phase more than once per frame has no further effect.
+    pub fn schedule(&mut self, cref: impl CRef, phase: Phase) {
+        let cref = UntypedComponentRef(cref.id());
+        let scheduled = self.scheduled.entry(phase).or_default();
+        if !scheduled.contains(&cref) {
+            scheduled.push(cref);
+        }
+    }
+
+    /// Executes every [scheduled](Globals::schedule) phase in order, so that all layout completes before any painting.
+    ///
+    /// Within a phase, components run in the order they were scheduled.
+    /// Components scheduled for the current phase while it runs are executed in this frame as well;
+    /// those scheduled for an earlier phase wait for the next frame.
+    pub fn run_frame(&mut self) {
+        for &phase in &Phase::ALL {
+            while let Some(scheduled) = self.scheduled.remove(&phase) {
+                for cref in scheduled {
+                    // unmounting doesn't unschedule, so stale entries are skipped here instead
+                    if !self.map.contains(cref.0) {
+                        continue;
+                    }
+
+                    self.invoke(&cref, |component, globals| {
+                        component.run_phase(globals, phase)
+                    });
+                    if phase == Phase::Paint {
+                        self.schedule_repaint(cref);
+                    }
                 }
             }
         }
     }
 
+    /// Same as [`update`](Globals::update), but returns an error if `cref` is invalid.
+    pub fn try_update(
+        &mut self,
+        cref: impl CRef,
+        repaint: Repaint,
+        propagate: Propagate,
+    ) -> Result<(), CoreError> {
+        if !self.map.contains(cref.id()) {
+            return Err(CoreError::InvalidReference(UntypedComponentRef(cref.id())));
+        }
+        self.update(cref, repaint, propagate);
+        Ok(())
+    }
+
+    /// Delivers `message` to the [`handle_message`](Component::handle_message) of `cref`.
+    ///
+    /// The concrete type of `cref` needn't be known, so this works with [`UntypedComponentRef`] (e.g. a container notifying all its children).
+    pub fn send(&mut self, cref: impl CRef, message: &dyn Any) {
+        self.invoke(&cref, |component, globals| {
+            component.handle_message(globals, message)
+        });
+    }
+
+    /// Same as [`send`](Globals::send), but returns an error if `cref` is invalid or currently in use.
+    pub fn try_send(&mut self, cref: impl CRef, message: &dyn Any) -> Result<(), CoreError> {
+        self.check_available(&cref)?;
+        self.send(cref, message);
+        Ok(())
+    }
+
+    /// Collects the display commands of the subtree rooted at `cref` into a single list, ready to be drawn by a backend.
+    ///
+    /// Each component's [`display`](Component::display) output is followed by that of its children (in [paint order](Globals::paint_order)),
+    /// and every component is wrapped in a `Save`/`Restore` pair so that transforms don't leak into its siblings.
+    ///
+    /// The output of `display` is cached per component, so it's only invoked again for components which were marked for repaint
+    /// (i.e. mounted or updated with [`Repaint::Yes`](Repaint::Yes)) since they were last rendered.
+    pub fn render(&mut self, cref: impl CRef) -> Vec<gfx::DisplayCommand> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "render",
+            component = self.untyped_internal_node(&cref).type_name(),
+            id = cref.id()
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        let mut out = Vec::new();
+        self.render_impl(UntypedComponentRef(cref.id()), &mut out);
+        out
+    }
+
+    /// Returns every component which was mounted or updated with [`Repaint::Yes`](Repaint::Yes) since the last call, in no particular order.
+    ///
+    /// A renderer can use this to only re-record the display lists of components which changed.
     #[inline]
-    fn untyped_internal_node(&self, cref: &impl CRef) -> &Box<dyn InternalNode> {
-        self.map.get(&cref.id()).expect("invalid reference")
+    pub fn take_dirty(&mut self) -> Vec<UntypedComponentRef> {
+        self.dirty.drain().collect()
     }
 
+    /// Returns `true` if a callback of `cref` has panicked since it was mounted (or since [`clear_poison`](Globals::clear_poison)).
+    ///
+    /// A poisoned component has been restored but may be in an inconsistent state; applications may want to replace or unmount it.
     #[inline]
-    fn untyped_internal_node_mut(&mut self, cref: &impl CRef) -> &mut Box<dyn InternalNode> {
-        self.map.get_mut(&cref.id()).expect("invalid reference")
+    pub fn is_poisoned(&self, cref: impl CRef) -> bool {
+        self.poisoned.contains(&UntypedComponentRef(cref.id()))
     }
-}
 
-impl Drop for Globals {
-    fn drop(&mut self) {
-        let keys: Vec<_> = self.map.keys().map(|x| x.clone()).collect();
-        for key in keys {
-            if self.map.contains_key(&key) {
-                self.unmount(UntypedComponentRef(key));
-            }
-        }
+    /// Clears the [poisoned](Globals::is_poisoned) mark of `cref`.
+    #[inline]
+    pub fn clear_poison(&mut self, cref: impl CRef) {
+        self.poisoned.remove(&UntypedComponentRef(cref.id()));
+    }
+
+    /// Returns a new painter from the current theme.
+    #[inline]
+    pub fn painter<T: Component>(&self, p: &'static str) -> theme::Painter<T> {
+        theme::get_painter(self.theme.as_ref(), p)
+    }
+
+    /// Changes the current theme.
+    ///
+    /// Components will only update their painters if they correctly handle `on_theme_changed`.
+    pub fn set_theme(&mut self, theme: impl theme::Theme + 'static) {
+        self.theme = Box::new(theme);
+        self.emit(self.on_theme_changed, &());
+    }
+
+    /// Returns the current accessibility preferences.
+    #[inline]
+    pub fn accessibility_prefs(&self) -> AccessibilityPrefs {
+        self.accessibility
+    }
+
+    /// Changes the accessibility preferences.
+    ///
+    /// The theme is notified first, then `on_accessibility_changed` and `on_theme_changed` are emitted so components can repaint.
+    pub fn set_accessibility_prefs(&mut self, prefs: AccessibilityPrefs) {
+        if prefs == self.accessibility {
+            return;
+        }
+
+        self.accessibility = prefs;
+        self.theme.accessibility_changed(prefs);
+        self.emit(self.on_accessibility_changed, &prefs);
+        self.emit(self.on_theme_changed, &());
+    }
+
+    /// Asks assistive technology to speak `text`, regardless of focus (e.g. "3 results found").
+    pub fn announce(&mut self, text: impl Into<String>, politeness: Politeness) {
+        let announcement = Announcement {
+            text: text.into(),
+            politeness,
+        };
+        self.emit(self.on_announce, &announcement);
+    }
+
+    /// Applies a command and records it in the undo stack.
+    ///
+    /// Any commands which were undone can no longer be redone.
+    pub fn execute(&mut self, mut command: impl commands::Command + 'static) {
+        let state = self.undo_state();
+        command.apply(self);
+        self.undo_stack.push(Box::new(command));
+        self.emit_undo_state(state);
+    }
+
+    /// Undoes the most recent command (or transaction), returning `false` if there was nothing to undo.
+    ///
+    /// Nothing is undone while a transaction is in progress.
+    pub fn undo(&mut self) -> bool {
+        let state = self.undo_state();
+        let mut group = match self.undo_stack.pop_undo() {
+            Some(group) => group,
+            None => return false,
+        };
+        for command in group.iter_mut().rev() {
+            command.undo(self);
+        }
+        self.undo_stack.push_undone(group);
+        self.emit_undo_state(state);
+        true
+    }
+
+    /// Redoes the most recently undone command (or transaction), returning `false` if there was nothing to redo.
+    ///
+    /// Nothing is redone while a transaction is in progress.
+    pub fn redo(&mut self) -> bool {
+        let state = self.undo_state();
+        let mut group = match self.undo_stack.pop_redo() {
+            Some(group) => group,
+            None => return false,
+        };
+        for command in group.iter_mut() {
+            command.apply(self);
+        }
+        self.undo_stack.push_done(group);
+        self.emit_undo_state(state);
+        true
+    }
+
+    /// Starts grouping executed commands into a single transaction which is undone/redone as one.
+    ///
+    /// Transactions may be nested; only the outermost [`end_transaction`](Globals::end_transaction) completes the group.
+    #[inline]
+    pub fn begin_transaction(&mut self) {
+        self.undo_stack.begin();
+    }
+
+    /// Ends the transaction started by the matching [`begin_transaction`](Globals::begin_transaction).
+    pub fn end_transaction(&mut self) {
+        let state = self.undo_state();
+        self.undo_stack.end();
+        self.emit_undo_state(state);
+    }
+
+    /// Discards all undo/redo history.
+    ///
+    /// Any transaction in progress is discarded as well, along with the commands it recorded.
+    pub fn clear_undo_history(&mut self) {
+        let state = self.undo_state();
+        self.undo_stack.clear();
+        self.emit_undo_state(state);
+    }
+
+    /// Returns the undo stack.
+    #[inline]
+    pub fn undo_stack(&self) -> &commands::UndoStack {
+        &self.undo_stack
+    }
+
+    /// Returns a new proxy which can schedule commands from other threads.
+    #[inline]
+    pub fn proxy(&self) -> GlobalsProxy {
+        GlobalsProxy {
+            queue: Arc::clone(&self.proxy_queue),
+        }
+    }
+
+    /// Executes every command queued through a [`GlobalsProxy`](GlobalsProxy), in the order they were scheduled, returning how many were executed.
+    ///
+    /// Commands scheduled while pumping are left for the next call.
+    pub fn pump(&mut self) -> usize {
+        let commands = std::mem::take(&mut *self.proxy_queue.lock().expect("proxy queue poisoned"));
+        let count = commands.len();
+        for command in commands {
+            command(self);
+        }
+        count
+    }
+
+    /// Returns the performance counters accumulated since the last [`take_perf_counters`](Globals::take_perf_counters).
+    #[inline]
+    pub fn perf_counters(&self) -> perf::Counters {
+        self.perf
+    }
+
+    /// Returns the accumulated performance counters and resets them to zero.
+    #[inline]
+    pub fn take_perf_counters(&mut self) -> perf::Counters {
+        std::mem::take(&mut self.perf)
+    }
+
+    /// Returns the component timings gathered since the last [`reset_profile`](Globals::reset_profile).
+    #[cfg(feature = "profiler")]
+    #[inline]
+    pub fn profile_report(&self) -> &perf::ProfileReport {
+        &self.profile
+    }
+
+    /// Discards all gathered component timings.
+    #[cfg(feature = "profiler")]
+    #[inline]
+    pub fn reset_profile(&mut self) {
+        self.profile = Default::default();
+    }
+
+    /// Summarizes what the UI tree is holding on to, which is useful for hunting leaks in long-running applications.
+    pub fn memory_report(&self) -> perf::MemoryReport {
+        let mut report = perf::MemoryReport {
+            signals: self.signal_map.ids().count(),
+            properties: self.properties.ids().count(),
+            ..Default::default()
+        };
+
+        for id in self.map.ids() {
+            let node = self.map.get(id).unwrap();
+            report.nodes += 1;
+            report.listeners += node.listener_count();
+            report.display_bytes += node.cached_display().map_or(0, |cmds| cmds.len())
+                * std::mem::size_of::<gfx::DisplayCommand>();
+            *report.instances.entry(node.type_name()).or_default() += 1;
+        }
+
+        report
+    }
+
+    /// Captures a read-only, `Send` copy of the tree structure.
+    ///
+    /// This lets other threads inspect the UI without holding on to `Globals`.
+    pub fn freeze(&self) -> TreeSnapshot {
+        let mut snapshot = TreeSnapshot::default();
+
+        for id in self.map.ids() {
+            let node = self.map.get(id).unwrap();
+            let cref = UntypedComponentRef(id);
+            if node.parent() == cref {
+                snapshot.roots.push(cref);
+            }
+            snapshot.nodes.insert(
+                cref,
+                SnapshotNode {
+                    parent: node.parent(),
+                    children: node.children().to_vec(),
+                    type_name: node.type_name(),
+                    listeners: node.listener_count(),
+                },
+            );
+        }
+
+        snapshot
+    }
+
+    /// Formats the subtree rooted at `cref` for debugging, listing each component's type name, ID, child count and listener count.
+    #[inline]
+    pub fn debug_tree(&self, cref: impl CRef) -> String {
+        self.freeze().dump(cref)
+    }
+
+    /// Creates a new signal owned by `cref`, which is [removed](Globals::remove_signal) when `cref` is unmounted.
+    ///
+    /// Prefer this over [`signal`](Globals::signal) for signals exposed by components (e.g. `on_click`), so they don't outlive them.
+    pub fn component_signal<T: 'static>(&mut self, cref: impl CRef) -> SignalRef<T> {
+        let sref = self.signal();
+        self.untyped_internal_node_mut(&cref).own_signal(sref.0);
+        sref
+    }
+
+    /// Removes a signal along with all of its listeners.
+    pub fn remove_signal<T: 'static>(&mut self, sref: SignalRef<T>) {
+        self.remove_signals(&[sref.0]);
+    }
+
+    /// Creates a new signal.
+    pub fn signal<T: 'static>(&mut self) -> SignalRef<T> {
+        SignalRef(
+            self.signal_map.insert(SignalSlot {
+                signal: Some(Box::new(signal::Signal::<T>::new())),
+                pending_removal: Vec::new(),
+            }),
+            Default::default(),
+        )
+    }
+
+    /// Emits an event for a signal.
+    ///
+    /// Nothing happens if the signal is already being emitted further up the call trace; use [`emit_deferred`](Globals::emit_deferred) for such re-entrant emissions.
+    ///
+    /// Components must not emit from within [`update`](Component::update) or [`display`](Component::display); with debug assertions
+    /// enabled this panics, naming the component (and is then reported through [`on_component_panic`](Globals::on_component_panic)).
+    /// Signals owned by `Globals` are exempt, as are emits made by listeners further down the call trace.
+    pub fn emit<T: 'static>(&mut self, sref: SignalRef<T>, event: &T) {
+        let _ = self.try_emit(sref, event);
+    }
+
+    /// Emits an event for a signal once every emission currently in progress has completed, or right away if there are none.
+    ///
+    /// Unlike [`emit`](Globals::emit), this is safe to use for a signal which is already being emitted further up the call trace,
+    /// e.g. a click handler which triggers another click. Deferred events are dispatched in the order they were queued.
+    pub fn emit_deferred<T: 'static>(&mut self, sref: SignalRef<T>, event: T) {
+        if self.emit_depth == 0 {
+            self.emit(sref, &event);
+        } else {
+            self.deferred_emits
+                .push(Box::new(move |globals| globals.emit(sref, &event)));
+        }
+    }
+
+    /// Same as [`emit`](Globals::emit), but returns an error if the signal doesn't exist or is already being emitted.
+    pub fn try_emit<T: 'static>(&mut self, sref: SignalRef<T>, event: &T) -> Result<(), CoreError> {
+        self.emit_impl(sref, event, false).map(|_| ())
+    }
+
+    /// Emits an event for a signal, stopping as soon as a listener [marks it handled](Globals::mark_handled).
+    ///
+    /// Listeners are invoked in [priority order](Globals::listen_with_priority), so the listeners which should get the first chance
+    /// to consume the event (e.g. the innermost of nested click areas, or a focused widget's keyboard shortcuts) should have the highest priority.
+    ///
+    /// Nothing happens (and [`Unhandled`](EmitResult::Unhandled) is returned) if the signal is already being emitted further up the call trace.
+    pub fn emit_until_handled<T: 'static>(&mut self, sref: SignalRef<T>, event: &T) -> EmitResult {
+        match self.emit_impl(sref, event, true) {
+            Ok(true) => EmitResult::Handled,
+            _ => EmitResult::Unhandled,
+        }
+    }
+
+    /// Marks the event currently being dispatched to the calling listener as consumed.
+    ///
+    /// If it was emitted through [`emit_until_handled`](Globals::emit_until_handled), no further listeners receive it.
+    /// Each emission is tracked separately, so marking an event emitted from within a listener doesn't affect the outer one.
+    /// Does nothing outside of a listener.
+    pub fn mark_handled(&mut self) {
+        if let Some(handled) = self.handled.last_mut() {
+            *handled = true;
+        }
+    }
+
+    /// Creates a signal which forwards every event of `src`, transformed by `f`.
+    ///
+    /// The derived signal is removed along with `src`. Removing it earlier stops the forwarding.
+    /// A panic within `f` propagates to whoever emitted `src`.
+    ///
+    /// # Panics
+    /// Panics if `src` is invalid or currently being emitted.
+    pub fn map_signal<A: 'static, B: 'static>(
+        &mut self,
+        src: SignalRef<A>,
+        f: impl Fn(&A) -> B + 'static,
+    ) -> SignalRef<B> {
+        let dst = self.signal();
+        self.derive_signal(src, dst, move |globals, event| {
+            globals.emit(dst, &f(event));
+        });
+        dst
+    }
+
+    /// Creates a signal which forwards the events of `src` for which `predicate` returns `true`.
+    ///
+    /// The derived signal is removed along with `src`. Removing it earlier stops the forwarding.
+    /// A panic within `predicate` propagates to whoever emitted `src`.
+    ///
+    /// # Panics
+    /// Panics if `src` is invalid or currently being emitted.
+    pub fn filter_signal<T: 'static>(
+        &mut self,
+        src: SignalRef<T>,
+        predicate: impl Fn(&T) -> bool + 'static,
+    ) -> SignalRef<T> {
+        let dst = self.signal();
+        self.derive_signal(src, dst, move |globals, event| {
+            if predicate(event) {
+                globals.emit(dst, event);
+            }
+        });
+        dst
+    }
+
+    /// Creates a new observable property with an initial value.
+    pub fn property<T: 'static>(&mut self, value: T) -> PropertyRef<T> {
+        PropertyRef {
+            id: self.properties.insert(Rc::new(value)),
+            on_changed: self.signal(),
+        }
+    }
+
+    /// Removes a property along with its [`on_changed`](PropertyRef::on_changed) signal.
+    pub fn remove_property<T: 'static>(&mut self, prop: PropertyRef<T>) {
+        self.properties
+            .remove(prop.id)
+            .expect("invalid property ref");
+        self.remove_signal(prop.on_changed);
+    }
+
+    /// Returns the current value of a property.
+    pub fn value<T: 'static>(&self, prop: PropertyRef<T>) -> &T {
+        self.properties
+            .get(prop.id)
+            .expect("invalid property ref")
+            .downcast_ref::<T>()
+            .unwrap()
+    }
+
+    /// Changes the value of a property and emits its [`on_changed`](PropertyRef::on_changed).
+    ///
+    /// The new value is stored before `on_changed` is emitted, so a listener changing the property again has the last word.
+    /// Such a nested change is [deferred](Globals::emit_deferred) until the current emission is over, so every listener ends up seeing the final value.
+    pub fn set_value<T: 'static>(&mut self, prop: PropertyRef<T>, value: T) {
+        let value: Rc<dyn Any> = Rc::new(value);
+        *self
+            .properties
+            .get_mut(prop.id)
+            .expect("invalid property ref") = Rc::clone(&value);
+
+        let on_changed = prop.on_changed;
+        if let Err(CoreError::SignalInUse(_)) =
+            self.try_emit(on_changed, value.downcast_ref::<T>().unwrap())
+        {
+            // set from one of the listeners; the remaining ones still see the old value, so they're sent the new one afterwards
+            self.deferred_emits.push(Box::new(move |globals| {
+                globals.emit(on_changed, value.downcast_ref::<T>().unwrap())
+            }));
+        }
+    }
+
+    /// Binds a property to a component.
+    ///
+    /// `apply` is invoked right away with the current value, then again whenever the property changes, after which the component is updated and repainted.
+    /// Changes made while the component is in use (e.g. by the component itself) are not applied to it.
+    pub fn bind<T: 'static, C: Component>(
+        &mut self,
+        prop: PropertyRef<T>,
+        cref: ComponentRef<C>,
+        apply: impl Fn(&mut C, &T) + 'static,
+    ) {
+        apply(
+            self.map
+                .get_mut(cref.0)
+                .expect("invalid reference")
+                .as_any_mut()
+                .downcast_mut::<ComponentNode<C>>()
+                .expect("mismatching reference type")
+                .component
+                .as_mut()
+                .expect("a reference to the component is already being used"),
+            self.properties
+                .get(prop.id)
+                .expect("invalid property ref")
+                .downcast_ref::<T>()
+                .unwrap(),
+        );
+        self.update(cref, Repaint::Yes, Propagate::No);
+
+        self.listen(prop.on_changed, cref, move |globals, value| {
+            if globals.is_available(cref) {
+                apply(globals.get_mut(cref), value);
+                globals.update(cref, Repaint::Yes, Propagate::No);
+            }
+        });
+    }
+
+    /// Sets a property on a node, emitting the key's [change signal](Globals::on_prop_changed).
+    ///
+    /// Node properties are meant for cross-cutting attributes which aren't fields of every component type.
+    /// They are dropped along with the node.
+    pub fn set_prop<T: 'static>(&mut self, cref: impl CRef, key: PropKey<T>, value: T) {
+        self.untyped_internal_node_mut(&cref)
+            .props_mut()
+            .insert(key.0, Box::new(value));
+        self.emit_prop_changed(key.0, UntypedComponentRef(cref.id()));
+    }
+
+    /// Returns a property of a node, or `None` if it isn't set (or was set with a different type).
+    pub fn get_prop<T: 'static>(&self, cref: impl CRef, key: PropKey<T>) -> Option<&T> {
+        self.untyped_internal_node(&cref)
+            .props()
+            .get(key.0)?
+            .downcast_ref::<T>()
+    }
+
+    /// Removes a property from a node, emitting the key's [change signal](Globals::on_prop_changed) if it was set.
+    pub fn remove_prop<T: 'static>(&mut self, cref: impl CRef, key: PropKey<T>) -> Option<T> {
+        let value = self
+            .untyped_internal_node_mut(&cref)
+            .props_mut()
+            .remove(key.0)?;
+        self.emit_prop_changed(key.0, UntypedComponentRef(cref.id()));
+        value.downcast::<T>().ok().map(|x| *x)
+    }
+
+    /// Returns the signal emitted with the node whenever the property `key` is set or removed on any node.
+    pub fn on_prop_changed<T>(&mut self, key: PropKey<T>) -> SignalRef<UntypedComponentRef> {
+        if let Some(&sref) = self.prop_signals.get(key.0) {
+            return sref;
+        }
+        let sref = self.internal_signal();
+        self.prop_signals.insert(key.0, sref);
+        sref
+    }
+
+    /// Returns the signal emitted with the new child whenever a component is added to the children of `cref`.
+    ///
+    /// This covers creating children as well as moving existing subtrees under `cref` (e.g. through [`reparent`](Globals::reparent) or [`attach`](Globals::attach)).
+    /// The signal is owned by `cref`, so it is removed along with it.
+    pub fn on_child_added(&mut self, cref: impl CRef) -> SignalRef<UntypedComponentRef> {
+        self.child_signals(UntypedComponentRef(cref.id())).added
+    }
+
+    /// Returns the signal emitted with the old child whenever a component is removed from the children of `cref`.
+    ///
+    /// This covers unmounting children as well as moving them elsewhere. For unmounted children, the signal is
+    /// emitted after the removal completes, at which point the child is no longer valid.
+    /// The signal is owned by `cref`, so it is removed along with it.
+    pub fn on_child_removed(&mut self, cref: impl CRef) -> SignalRef<UntypedComponentRef> {
+        self.child_signals(UntypedComponentRef(cref.id())).removed
+    }
+
+    /// Stashes arbitrary data on a node, returning whatever was previously stored under `key`.
+    ///
+    /// Unlike [node properties](Globals::set_prop), metadata is untyped and changes aren't observable;
+    /// it is meant for layout engines, test harnesses and debug tooling. It is dropped along with the node.
+    pub fn set_meta(
+        &mut self,
+        cref: impl CRef,
+        key: impl Into<String>,
+        value: Box<dyn Any>,
+    ) -> Option<Box<dyn Any>> {
+        self.untyped_internal_node_mut(&cref)
+            .meta_mut()
+            .insert(key.into(), value)
+    }
+
+    /// Returns the metadata stored on a node under `key`.
+    #[inline]
+    pub fn get_meta(&self, cref: impl CRef, key: &str) -> Option<&dyn Any> {
+        self.untyped_internal_node(&cref)
+            .meta()
+            .get(key)
+            .map(|x| &**x)
+    }
+
+    /// Mutably returns the metadata stored on a node under `key`.
+    #[inline]
+    pub fn get_meta_mut(&mut self, cref: impl CRef, key: &str) -> Option<&mut dyn Any> {
+        self.untyped_internal_node_mut(&cref)
+            .meta_mut()
+            .get_mut(key)
+            .map(|x| &mut **x)
+    }
+
+    /// Removes the metadata stored on a node under `key`.
+    #[inline]
+    pub fn remove_meta(&mut self, cref: impl CRef, key: &str) -> Option<Box<dyn Any>> {
+        self.untyped_internal_node_mut(&cref).meta_mut().remove(key)
+    }
+
+    /// Makes `value` available to `cref` and all of its descendants through [`consume`](Globals::consume).
+    ///
+    /// Values are keyed by type, so providing another `T` on the same node replaces (and returns) the previous one.
+    pub fn provide<T: 'static>(&mut self, cref: impl CRef, value: T) -> Option<T> {
+        self.untyped_internal_node_mut(&cref)
+            .contexts_mut()
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .map(|x| *x.downcast::<T>().unwrap())
+    }
+
+    /// Returns the value of type `T` provided by the nearest of `cref` and its ancestors, or `None` if none provide one.
+    pub fn consume<T: 'static>(&self, cref: impl CRef) -> Option<&T> {
+        let mut current = UntypedComponentRef(cref.id());
+        loop {
+            let node = self.untyped_internal_node(&current);
+            if let Some(value) = node.contexts().get(&TypeId::of::<T>()) {
+                return value.downcast_ref::<T>();
+            }
+            if node.parent() == current {
+                return None;
+            }
+            current = node.parent();
+        }
+    }
+
+    /// Sets the z-index of a component; siblings with a higher z-index are drawn above those with a lower one.
+    ///
+    /// Siblings with equal z-indices keep their child order. This is stored as the [`Z_INDEX`](Z_INDEX) node property.
+    #[inline]
+    pub fn set_z_index(&mut self, cref: impl CRef, z_index: i32) {
+        self.set_prop(cref, Z_INDEX, z_index);
+    }
+
+    /// Returns the z-index of a component, which is zero unless set.
+    #[inline]
+    pub fn z_index(&self, cref: impl CRef) -> i32 {
+        self.get_prop(cref, Z_INDEX).cloned().unwrap_or_default()
+    }
+
+    /// Brings a component above all of its siblings.
+    pub fn raise(&mut self, cref: impl CRef) {
+        let cref = UntypedComponentRef(cref.id());
+        let (_, max) = self.sibling_z_range(cref);
+        if let Some(max) = max {
+            if self.z_index(cref) <= max {
+                self.set_z_index(cref, max.saturating_add(1));
+            }
+        }
+    }
+
+    /// Sends a component below all of its siblings.
+    pub fn lower(&mut self, cref: impl CRef) {
+        let cref = UntypedComponentRef(cref.id());
+        let (min, _) = self.sibling_z_range(cref);
+        if let Some(min) = min {
+            if self.z_index(cref) >= min {
+                self.set_z_index(cref, min.saturating_sub(1));
+            }
+        }
+    }
+
+    /// Returns the children of `pcref` in the order they are drawn, i.e. sorted by [z-index](Globals::z_index).
+    ///
+    /// Hit testing should walk this in reverse, so that the topmost component is found first.
+    pub fn paint_order(&self, pcref: impl CRef) -> Vec<UntypedComponentRef> {
+        let mut children = self.untyped_internal_node(&pcref).children().to_vec();
+        children.sort_by_key(|child| self.z_index(*child));
+        children
+    }
+
+    /// Adds a managed listener to a signal.
+    ///
+    /// "Managed" implies that the listener will be removed when `cref` is unmounted.
+    ///
+    /// If the listener panics, the panic is caught and reported through [`on_component_panic`](Globals::on_component_panic) on behalf of `cref`.
+    ///
+    /// # Panics
+    /// Panics if [`try_listen`](Globals::try_listen) would return an error.
+    pub fn listen<T: 'static, C: Component>(
+        &mut self,
+        sref: SignalRef<T>,
+        cref: ComponentRef<C>,
+        listener: impl Fn(&mut Globals, &T) + 'static,
+    ) {
+        if let Err(err) = self.try_listen(sref, cref, listener) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Adds a managed listener which is handed the component behind `cref`, so that it can mutate it directly.
+    ///
+    /// The component is taken out of the tree for the duration of the handler, as with [`with`](Globals::with).
+    /// If the component is already in use when the signal is emitted (e.g. it emitted the signal itself), the handler is
+    /// skipped for that emission.
+    ///
+    /// # Panics
+    /// Panics if [`try_listen`](Globals::try_listen) would return an error.
+    pub fn connect<T: 'static, C: Component>(
+        &mut self,
+        sref: SignalRef<T>,
+        cref: ComponentRef<C>,
+        handler: impl Fn(&mut C, &mut Globals, &T) + 'static,
+    ) {
+        self.listen(sref, cref, move |globals, event| {
+            if globals.is_available(cref) {
+                globals.with(cref, |component, globals| {
+                    handler(component, globals, event)
+                });
+            }
+        });
+    }
+
+    /// Same as [`listen`](Globals::listen), but the listener is invoked before every listener of lower `priority` (the default being `0`).
+    ///
+    /// Listeners of equal priority are invoked in the order they were added.
+    /// For example, a validation listener can be given a higher priority than a submission listener so it always runs first.
+    ///
+    /// # Panics
+    /// Panics if [`try_listen_with_priority`](Globals::try_listen_with_priority) would return an error.
+    pub fn listen_with_priority<T: 'static, C: Component>(
+        &mut self,
+        sref: SignalRef<T>,
+        cref: ComponentRef<C>,
+        priority: i32,
+        listener: impl Fn(&mut Globals, &T) + 'static,
+    ) {
+        if let Err(err) = self.try_listen_with_priority(sref, cref, priority, listener) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Same as [`listen`](Globals::listen), but returns an error rather than panicking if `sref` or `cref` can't be used.
+    #[inline]
+    pub fn try_listen<T: 'static, C: Component>(
+        &mut self,
+        sref: SignalRef<T>,
+        cref: ComponentRef<C>,
+        listener: impl Fn(&mut Globals, &T) + 'static,
+    ) -> Result<(), CoreError> {
+        self.try_listen_with_priority(sref, cref, 0, listener)
+    }
+
+    /// Same as [`listen_with_priority`](Globals::listen_with_priority), but returns an error rather than panicking if `sref` or `cref` can't be used.
+    #[inline]
+    pub fn try_listen_with_priority<T: 'static, C: Component>(
+        &mut self,
+        sref: SignalRef<T>,
+        cref: ComponentRef<C>,
+        priority: i32,
+        listener: impl Fn(&mut Globals, &T) + 'static,
+    ) -> Result<(), CoreError> {
+        self.listen_managed(sref, cref, priority, listener)
+    }
+
+    /// Same as [`listen`](Globals::listen), but the listener may mutate its own captured state (e.g. a counter or a debounce timestamp).
+    ///
+    /// # Panics
+    /// Panics if [`try_listen_mut`](Globals::try_listen_mut) would return an error.
+    pub fn listen_mut<T: 'static, C: Component>(
+        &mut self,
+        sref: SignalRef<T>,
+        cref: ComponentRef<C>,
+        listener: impl FnMut(&mut Globals, &T) + 'static,
+    ) {
+        if let Err(err) = self.try_listen_mut(sref, cref, listener) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Same as [`listen_mut`](Globals::listen_mut), but returns an error rather than panicking if `sref` or `cref` can't be used.
+    #[inline]
+    pub fn try_listen_mut<T: 'static, C: Component>(
+        &mut self,
+        sref: SignalRef<T>,
+        cref: ComponentRef<C>,
+        listener: impl FnMut(&mut Globals, &T) + 'static,
+    ) -> Result<(), CoreError> {
+        self.listen_managed(sref, cref, 0, listener)
+    }
+}
+
+impl Globals {
+    /// Adds a listener to a signal, detached when `cref` is unmounted and reporting panics on its behalf.
+    fn listen_managed<T: 'static, C: Component>(
+        &mut self,
+        sref: SignalRef<T>,
+        cref: ComponentRef<C>,
+        priority: i32,
+        mut listener: impl FnMut(&mut Globals, &T) + 'static,
+    ) -> Result<(), CoreError> {
+        if !self.map.contains(cref.0) {
+            return Err(CoreError::InvalidReference(UntypedComponentRef(cref.0)));
+        }
+        if !self.is_of_type(cref) {
+            return Err(CoreError::TypeMismatch(UntypedComponentRef(cref.0)));
+        }
+
+        let listener = move |globals: &mut Globals, event: &T| {
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| listener(globals, event)));
+            if let Err(payload) = result {
+                globals.report_panic(
+                    UntypedComponentRef(cref.0),
+                    std::any::type_name::<C>(),
+                    payload,
+                );
+            }
+        };
+        let listener = self.listen_unmanaged(sref, priority, listener)?;
+        self.node_mut(cref).listeners.push(ListenerPair {
+            listener,
+            signal: sref.0,
+        });
+        self.listening
+            .entry(sref.0)
+            .or_default()
+            .insert(UntypedComponentRef(cref.0));
+
+        Ok(())
+    }
+
+    /// Adds a listener to a signal without tying it to any component.
+    fn listen_unmanaged<T: 'static>(
+        &mut self,
+        sref: SignalRef<T>,
+        priority: i32,
+        listener: impl FnMut(&mut Globals, &T) + 'static,
+    ) -> Result<signal::ListenerRef, CoreError> {
+        Ok(self
+            .signal_map
+            .get_mut(sref.0)
+            .ok_or(CoreError::InvalidSignal(sref.0))?
+            .signal
+            .as_mut()
+            .ok_or(CoreError::SignalInUse(sref.0))?
+            .as_any_mut()
+            .downcast_mut::<signal::Signal<T>>()
+            .unwrap()
+            .insert(priority, Box::new(listener)))
+    }
+
+    /// Forwards `src` into `dst` through `forward`, recording `dst` as derived so that it's removed along with `src`.
+    fn derive_signal<A: 'static, B: 'static>(
+        &mut self,
+        src: SignalRef<A>,
+        dst: SignalRef<B>,
+        forward: impl Fn(&mut Globals, &A) + 'static,
+    ) {
+        match self.listen_unmanaged(src, 0, forward) {
+            Ok(listener) => {
+                if self.internal_signals.contains(&src.0) {
+                    self.internal_signals.insert(dst.0);
+                }
+                self.derived.entry(src.0).or_default().push(dst.0);
+                self.forwarders.insert(
+                    dst.0,
+                    ListenerPair {
+                        listener,
+                        signal: src.0,
+                    },
+                );
+            }
+            Err(err) => {
+                self.signal_map.remove(dst.0);
+                panic!("{}", err);
+            }
+        }
+    }
+
+    fn mount_child<T: Component>(
+        &mut self,
+        pcref: impl CRef,
+        index: Option<usize>,
+        new: impl FnOnce(&mut Globals, ComponentRef<T>) -> T,
+    ) -> ComponentRef<T> {
+        let len = self.untyped_internal_node(&pcref).children().len();
+        let index = index.unwrap_or(len);
+        assert!(index <= len, "child index out of bounds");
+
+        let node = ComponentNode::<T>::new(UntypedComponentRef(pcref.id()));
+        let cref = ComponentRef(self.map.insert(Box::new(node)), Default::default());
+
+        self.untyped_internal_node_mut(&pcref)
+            .children_mut()
+            .insert(index, UntypedComponentRef(cref.0));
+
+        self.perf.mounts += 1;
+        self.dirty.insert(UntypedComponentRef(cref.0));
+        self.node_mut(cref).component = Some(new(self, cref));
+        self.debug_validate(cref.0);
+        self.finish_mount(UntypedComponentRef(cref.0));
+        self.emit_child_changed(
+            UntypedComponentRef(pcref.id()),
+            UntypedComponentRef(cref.0),
+            true,
+        );
+
+        cref
+    }
+
+    /// Returns the child signals of `cref`, creating them if needed.
+    fn child_signals(&mut self, cref: UntypedComponentRef) -> ChildSignals {
+        if let Some(&signals) = self.child_signals.get(&cref) {
+            return signals;
+        }
+        let signals = ChildSignals {
+            added: self.component_signal(cref),
+            removed: self.component_signal(cref),
+        };
+        self.internal_signals.insert(signals.added.0);
+        self.internal_signals.insert(signals.removed.0);
+        self.child_signals.insert(cref, signals);
+        signals
+    }
+
+    /// Emits `on_child_added` or `on_child_removed` of `parent`, if anything asked for them.
+    fn emit_child_changed(
+        &mut self,
+        parent: UntypedComponentRef,
+        child: UntypedComponentRef,
+        added: bool,
+    ) {
+        if let Some(signals) = self.child_signals.get(&parent) {
+            let sref = if added {
+                signals.added
+            } else {
+                signals.removed
+            };
+            self.emit(sref, &child);
+        }
+    }
+
+    /// Calls `f` with the component behind `cref` taken out of the tree, catching and reporting any panic.
+    fn invoke(&mut self, cref: &impl CRef, f: impl FnOnce(&mut dyn Component, &mut Globals)) {
+        let mut component = self.untyped_internal_node_mut(cref).take();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            f(&mut *component, self);
+        }));
+        self.untyped_internal_node_mut(cref).replace(component);
+
+        if let Err(payload) = result {
+            let type_name = self.untyped_internal_node(cref).type_name();
+            self.report_panic(UntypedComponentRef(cref.id()), type_name, payload);
+        }
+
+        // run any updates the component requested for itself (or others in use) while it was taken
+        if self.update_depth == 0 {
+            self.flush_update_queue();
+        }
+    }
+
+    /// Marks `cref` as needing to be repainted, unless it's part of a [frozen](Globals::set_frozen) subtree.
+    fn schedule_repaint(&mut self, cref: UntypedComponentRef) {
+        if !self.is_frozen(cref) {
+            self.untyped_internal_node_mut(&cref).repaint();
+            self.dirty.insert(cref);
+        }
+    }
+
+    /// Returns the root of the (possibly detached) tree containing `cref`.
+    fn root_of(&self, mut cref: UntypedComponentRef) -> UntypedComponentRef {
+        loop {
+            let parent = self.untyped_internal_node(&cref).parent();
+            if parent == cref {
+                return cref;
+            }
+            cref = parent;
+        }
+    }
+
+    /// Removes `cref` from its parent, leaving it as the root of a detached subtree.
+    fn detach_impl(&mut self, cref: UntypedComponentRef) {
+        let parent = self.untyped_internal_node(&cref).parent();
+        assert!(parent != cref, "cannot detach a root");
+
+        self.untyped_internal_node_mut(&parent).remove_child(cref);
+        self.untyped_internal_node_mut(&cref).set_parent(cref);
+        self.debug_validate(parent.0);
+        self.emit_child_changed(parent, cref, false);
+    }
+
+    /// Attaches the detached subtree rooted at `cref` to the end of `parent`'s children.
+    fn attach_impl(&mut self, cref: UntypedComponentRef, parent: UntypedComponentRef) {
+        self.untyped_internal_node_mut(&parent)
+            .children_mut()
+            .push(cref);
+        self.untyped_internal_node_mut(&cref).set_parent(parent);
+        self.debug_validate(cref.0);
+        self.emit_child_changed(parent, cref, true);
+    }
+
+    /// Returns the lowest and highest z-index among the siblings of `cref` (excluding itself).
+    fn sibling_z_range(&self, cref: UntypedComponentRef) -> (Option<i32>, Option<i32>) {
+        let parent = self.untyped_internal_node(&cref).parent();
+        if parent == cref {
+            return (None, None);
+        }
+
+        let siblings = self
+            .untyped_internal_node(&parent)
+            .children()
+            .iter()
+            .filter(|x| **x != cref)
+            .map(|x| self.z_index(*x));
+        siblings.fold((None, None), |(min, max), z| {
+            (
+                Some(min.map_or(z, |min: i32| min.min(z))),
+                Some(max.map_or(z, |max: i32| max.max(z))),
+            )
+        })
+    }
+
+    fn render_impl(&mut self, cref: UntypedComponentRef, out: &mut Vec<gfx::DisplayCommand>) {
+        if self.frozen.contains(&cref) {
+            return;
+        }
+
+        if self.untyped_internal_node(&cref).cached_display().is_none() {
+            #[cfg(feature = "profiler")]
+            let start = std::time::Instant::now();
+
+            let mut cmds = Vec::new();
+            #[cfg(debug_assertions)]
+            self.enter_hook(&cref);
+            self.invoke(&cref, |component, _| cmds = component.display());
+            #[cfg(debug_assertions)]
+            self.hooks.pop();
+
+            #[cfg(feature = "profiler")]
+            {
+                let type_name = self.untyped_internal_node(&cref).type_name();
+                self.profile
+                    .record(cref, type_name, start.elapsed(), |x| &mut x.display);
+            }
+
+            self.untyped_internal_node_mut(&cref).cache_display(cmds);
+        }
+
+        out.push(gfx::DisplayCommand::Save);
+        out.extend_from_slice(self.untyped_internal_node(&cref).cached_display().unwrap());
+        for child in self.paint_order(cref) {
+            self.render_impl(child, out);
+        }
+        out.push(gfx::DisplayCommand::Restore);
+    }
+
+    fn check_available(&self, cref: &impl CRef) -> Result<(), CoreError> {
+        let untyped = UntypedComponentRef(cref.id());
+        match self.map.get(cref.id()) {
+            None => Err(CoreError::InvalidReference(untyped)),
+            Some(node) if node.is_taken() => Err(CoreError::ComponentInUse(untyped)),
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn sibling_position(&self, sibling: &impl CRef) -> (UntypedComponentRef, usize) {
+        let parent = self.untyped_internal_node(sibling).parent();
+        assert!(parent.0 != sibling.id(), "the root has no siblings");
+        let index = self
+            .untyped_internal_node(&parent)
+            .children()
+            .iter()
+            .position(|child| child.0 == sibling.id())
+            .unwrap();
+        (parent, index)
+    }
+
+    fn flush_update_queue(&mut self) {
+        loop {
+            let queue = std::mem::take(&mut self.update_queue);
+            if queue.is_empty() {
+                break;
+            }
+
+            // components still in use further up the call trace stay queued until they're back in place
+            let mut stalled = Vec::new();
+            let mut progressed = false;
+            for (cref, repaint, propagate) in queue {
+                if !self.map.contains(cref.0) {
+                    continue;
+                }
+                if self.untyped_internal_node(&cref).is_taken() {
+                    stalled.push((cref, repaint, propagate));
+                    continue;
+                }
+                progressed = true;
+                self.update(cref, repaint, propagate);
+            }
+
+            stalled.append(&mut self.update_queue);
+            self.update_queue = stalled;
+            if !progressed {
+                break;
+            }
+        }
+    }
+
+    fn late_unmount_impl(&mut self, cref: impl CRef, v: &mut Vec<u64>) {
+        v.push(cref.id());
+        self.invoke(&cref, |component, globals| component.unmount(globals));
+
+        for child in self.untyped_internal_node(&cref).children().to_vec() {
+            self.late_unmount_impl(child, v);
+        }
+    }
+
+    fn unmount_single(&mut self, cref: &impl CRef) {
+        self.invoke(cref, |component, globals| component.unmount(globals));
+        self.remove_node(cref.id());
+    }
+
+    fn remove_node(&mut self, id: u64) {
+        if let Some(mut node) = self.map.remove(id) {
+            self.unindex_listeners(UntypedComponentRef(id), &node.listener_signals());
+            node.detach_listeners(self);
+            self.remove_signals(node.owned_signals());
+            if let Some(parent) = self.map.get_mut(node.parent().0) {
+                parent.remove_child(UntypedComponentRef(id));
+                if self.child_signals.contains_key(&node.parent()) {
+                    self.removed_children
+                        .push((node.parent(), UntypedComponentRef(id)));
+                }
+            }
+            self.perf.unmounts += 1;
+            #[cfg(feature = "profiler")]
+            self.profile.by_instance.remove(&UntypedComponentRef(id));
+            self.unmounted.push(LifecycleEvent {
+                cref: UntypedComponentRef(id),
+                type_name: node.type_name(),
+            });
+        }
+
+        for name in self
+            .node_names
+            .remove(&UntypedComponentRef(id))
+            .unwrap_or_default()
+        {
+            self.names.remove(&name);
+        }
+        self.poisoned.remove(&UntypedComponentRef(id));
+        self.dirty.remove(&UntypedComponentRef(id));
+        if let Some(type_id) = self.pooled.remove(&UntypedComponentRef(id)) {
+            if let Some(pooled) = self.pool.get_mut(&type_id) {
+                pooled.retain(|x| x.0 != id);
+            }
+        }
+        self.frozen.remove(&UntypedComponentRef(id));
+        self.child_signals.remove(&UntypedComponentRef(id));
+    }
+
+    /// Creates a signal owned by `Globals` itself, which components may emit from anywhere.
+    fn internal_signal<T: 'static>(&mut self) -> SignalRef<T> {
+        let sref = self.signal();
+        self.internal_signals.insert(sref.0);
+        sref
+    }
+
+    /// Returns the allocation count to measure an update or emission from, unless one further up the call trace is already being measured.
+    #[inline]
+    fn start_hot_path(&self) -> Option<u64> {
+        if self.update_depth == 0 && self.emit_depth == 0 {
+            Some(perf::allocations())
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn end_hot_path(&mut self, start: Option<u64>) {
+        if let Some(start) = start {
+            self.perf.allocations += perf::allocations() - start;
+        }
+    }
+
+    /// Records that `cref` is in one of the hooks which must not emit, along with the emission depth at the time.
+    #[cfg(debug_assertions)]
+    fn enter_hook(&mut self, cref: &impl CRef) {
+        let type_name = self.untyped_internal_node(cref).type_name();
+        self.hooks.push((type_name, self.emit_depth));
+    }
+
+    #[inline]
+    fn undo_state(&self) -> (bool, bool) {
+        (self.undo_stack.can_undo(), self.undo_stack.can_redo())
+    }
+
+    /// Emits the undo/redo signals for whichever state changed since `before`.
+    fn emit_undo_state(&mut self, before: (bool, bool)) {
+        let (can_undo, can_redo) = self.undo_state();
+        if can_undo != before.0 {
+            self.emit(self.on_can_undo_changed, &can_undo);
+        }
+        if can_redo != before.1 {
+            self.emit(self.on_can_redo_changed, &can_redo);
+        }
+    }
+
+    fn emit_prop_changed(&mut self, key: &'static str, cref: UntypedComponentRef) {
+        if let Some(&sref) = self.prop_signals.get(key) {
+            self.emit(sref, &cref);
+        }
+    }
+
+    fn report_panic(
+        &mut self,
+        cref: UntypedComponentRef,
+        type_name: &'static str,
+        payload: Box<dyn Any + Send>,
+    ) {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|x| x.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned());
+        self.poisoned.insert(cref);
+        self.emit(
+            self.on_component_panic,
+            &ComponentPanic {
+                cref,
+                type_name,
+                message,
+            },
+        );
+    }
+
+    /// Returns a stable key for every node, derived from its path of child indices from the root and its type.
+    #[cfg(feature = "persistence")]
+    fn persistence_keys(&self) -> Vec<(String, u64)> {
+        let mut keys = Vec::new();
+        let mut stack: Vec<_> = self
+            .map
+            .ids()
+            .filter(|&id| self.map.get(id).unwrap().parent().0 == id)
+            .enumerate()
+            .map(|(i, id)| (i.to_string(), id))
+            .collect();
+
+        while let Some((path, id)) = stack.pop() {
+            let node = self.map.get(id).unwrap();
+            for (i, child) in node.children().iter().enumerate() {
+                stack.push((format!("{}/{}", path, i), child.0));
+            }
+            keys.push((format!("{}:{}", path, node.type_name()), id));
+        }
+
+        keys
+    }
+
+    fn shutdown_impl(&mut self, cref: UntypedComponentRef, report: &mut TeardownReport) {
+        let children = self.untyped_internal_node(&cref).children().to_vec();
+        for child in children {
+            if self.map.contains(child.0) {
+                self.shutdown_impl(child, report);
+            }
+        }
+
+        let mut component = self.untyped_internal_node_mut(&cref).take();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            component.unmount(self);
+        }));
+        self.untyped_internal_node_mut(&cref).replace(component);
+        if result.is_err() {
+            report
+                .unmount_panics
+                .push((cref, self.untyped_internal_node(&cref).type_name()));
+        }
+        self.remove_node(cref.0);
+        self.flush_unmounted();
+    }
+
+    /// Dispatches an event, either to every listener or until one marks it handled, returning whether it was.
+    fn emit_impl<T: 'static>(
+        &mut self,
+        sref: SignalRef<T>,
+        event: &T,
+        until_handled: bool,
+    ) -> Result<bool, CoreError> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!("emit", event = std::any::type_name::<T>(), id = sref.0);
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        #[cfg(debug_assertions)]
+        {
+            // listeners further down the call trace are free to emit
+            if let Some(&(type_name, depth)) = self.hooks.last() {
+                if depth == self.emit_depth && !self.internal_signals.contains(&sref.0) {
+                    panic!(
+                        "{} emitted signal {} from within update or display; emit from listeners or event handlers instead",
+                        type_name, sref.0
+                    );
+                }
+            }
+        }
+
+        self.perf.emissions += 1;
+        let mut signal = self
+            .signal_map
+            .get_mut(sref.0)
+            .ok_or(CoreError::InvalidSignal(sref.0))?
+            .signal
+            .take()
+            .ok_or(CoreError::SignalInUse(sref.0))?;
+
+        let allocations = self.start_hot_path();
+        self.emit_depth += 1;
+        self.handled.push(false);
+        // managed listeners catch their own panics, but unmanaged ones (e.g. those of derived signals) don't
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let signal = signal
+                .as_any_mut()
+                .downcast_mut::<signal::Signal<T>>()
+                .unwrap();
+            if until_handled {
+                signal.emit_until(self, event, |globals| globals.handled.last() == Some(&true));
+            } else {
+                signal.emit(self, event);
+            }
+        }));
+        let handled = self.handled.pop() == Some(true) && until_handled;
+
+        // the signal may have been removed by one of its listeners
+        if let Some(slot) = self.signal_map.get_mut(sref.0) {
+            for listener in slot.pending_removal.drain(..) {
+                signal.detach(listener);
+            }
+            slot.signal = Some(signal);
+        }
+        self.emit_depth -= 1;
+
+        if let Err(payload) = result {
+            std::panic::resume_unwind(payload);
+        }
+
+        if self.emit_depth == 0 && !self.deferred_emits.is_empty() {
+            self.flush_deferred_emits();
+        }
+        self.end_hot_path(allocations);
+
+        Ok(handled)
+    }
+
+    /// Dispatches queued [deferred emissions](Globals::emit_deferred), including any queued along the way, in order.
+    fn flush_deferred_emits(&mut self) {
+        // counts as an emission, so that events deferred from here are queued behind the rest
+        self.emit_depth += 1;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            while !self.deferred_emits.is_empty() {
+                for emit in std::mem::take(&mut self.deferred_emits) {
+                    emit(self);
+                }
+            }
+        }));
+        self.emit_depth -= 1;
+
+        if let Err(payload) = result {
+            std::panic::resume_unwind(payload);
+        }
+    }
+
+    /// Removes signals, along with the records of every node listening to them.
+    fn remove_signals(&mut self, signals: &[u64]) {
+        for &signal in signals {
+            self.internal_signals.remove(&signal);
+            self.signal_map.remove(signal);
+            for cref in self.listening.remove(&signal).unwrap_or_default() {
+                if let Some(node) = self.map.get_mut(cref.0) {
+                    node.forget_signal(signal);
+                }
+            }
+            if let Some(derived) = self.derived.remove(&signal) {
+                self.remove_signals(&derived);
+            }
+            if let Some(forwarder) = self.forwarders.remove(&signal) {
+                forwarder.detach(self);
+                if let Some(derived) = self.derived.get_mut(&forwarder.signal) {
+                    derived.retain(|&x| x != signal);
+                }
+            }
+        }
+    }
+
+    /// Drops `cref` from the record of nodes listening to each of `signals`.
+    fn unindex_listeners(&mut self, cref: UntypedComponentRef, signals: &[u64]) {
+        for signal in signals {
+            if let Some(nodes) = self.listening.get_mut(signal) {
+                nodes.remove(&cref);
+                if nodes.is_empty() {
+                    self.listening.remove(signal);
+                }
+            }
+        }
+    }
+
+    /// Emits `on_unmounted` and `on_component_unmounted` (and `on_child_removed` of surviving parents) for every node removed since the last flush.
+    fn flush_unmounted(&mut self) {
+        for event in std::mem::take(&mut self.unmounted) {
+            self.emit(self.on_unmounted, &event.cref);
+            self.emit(self.on_component_unmounted, &event);
+        }
+        for (parent, child) in std::mem::take(&mut self.removed_children) {
+            self.emit_child_changed(parent, child, false);
+        }
+    }
+
+    /// Runs the [`mounted`](Component::mounted) hook of a newly constructed component and emits `on_component_mounted`.
+    fn finish_mount(&mut self, cref: UntypedComponentRef) {
+        self.invoke(&cref, |component, globals| component.mounted(globals));
+        let event = LifecycleEvent {
+            cref,
+            type_name: self.untyped_internal_node(&cref).type_name(),
+        };
+        self.emit(self.on_component_mounted, &event);
+    }
+
+    fn unmount_impl(&mut self, cref: &impl CRef, reverse: bool) {
+        let children = self.untyped_internal_node(cref).children().to_vec();
+
+        if !reverse {
+            self.unmount_single(cref);
+        }
+
+        for child in children {
+            if self.map.contains(child.0) {
+                self.unmount_impl(&child, reverse);
+            }
+        }
+
+        if reverse {
+            self.unmount_single(cref);
+        }
+    }
+
+    fn validate_node(&self, id: u64, check_taken: bool, violations: &mut Vec<Violation>) {
+        let node = match self.map.get(id) {
+            Some(node) => node,
+            None => return,
+        };
+        let cref = UntypedComponentRef(id);
+
+        let parent = node.parent();
+        if parent != cref
+            && !self
+                .map
+                .get(parent.0)
+                .map(|x| x.children().contains(&cref))
+                .unwrap_or(false)
+        {
+            violations.push(Violation::Orphaned {
+                node: cref,
+                node_type: node.type_name(),
+                parent,
+            });
+        }
+
+        for &child in node.children() {
+            match self.map.get(child.0) {
+                Some(child_node) if child_node.parent() != cref => {
+                    violations.push(Violation::ParentMismatch {
+                        child,
+                        child_type: child_node.type_name(),
+                        parent: cref,
+                        parent_type: node.type_name(),
+                        actual_parent: child_node.parent(),
+                    })
+                }
+                Some(_) => {}
+                None => violations.push(Violation::DanglingChild {
+                    parent: cref,
+                    parent_type: node.type_name(),
+                    child,
+                }),
+            }
+        }
+
+        for signal in node.listener_signals() {
+            if !self.signal_map.contains(signal) {
+                violations.push(Violation::DeadSignal {
+                    node: cref,
+                    node_type: node.type_name(),
+                    signal,
+                });
+            }
+        }
+
+        if check_taken && node.is_taken() {
+            violations.push(Violation::Taken {
+                node: cref,
+                node_type: node.type_name(),
+            });
+        }
+    }
+
+    /// Checks the structural invariants local to a node when debug assertions are enabled.
+    ///
+    /// Components may legitimately be taken at this point, so that isn't checked.
+    #[inline]
+    fn debug_validate(&self, id: u64) {
+        if cfg!(debug_assertions) {
+            let mut violations = Vec::new();
+            self.validate_node(id, false, &mut violations);
+            if !violations.is_empty() {
+                panic_on_violations(&violations);
+            }
+        }
+    }
+
+    #[inline]
+    fn untyped_internal_node(&self, cref: &impl CRef) -> &Box<dyn InternalNode> {
+        self.map.get(cref.id()).expect("invalid reference")
+    }
+
+    #[inline]
+    fn untyped_internal_node_mut(&mut self, cref: &impl CRef) -> &mut Box<dyn InternalNode> {
+        self.map.get_mut(cref.id()).expect("invalid reference")
+    }
+}
+
+fn panic_on_violations(violations: &[Violation]) -> ! {
+    let violations: Vec<_> = violations.iter().map(|x| x.to_string()).collect();
+    panic!("component tree is corrupt:\n{}", violations.join("\n"));
+}
+
+impl Drop for Globals {
+    fn drop(&mut self) {
+        let keys: Vec<_> = self.map.ids().collect();
+        for key in keys {
+            if self.map.contains(key) {
+                self.unmount(UntypedComponentRef(key));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test::MockTheme,
+        std::{cell::RefCell, rc::Rc},
+    };
+
+    struct Root;
+
+    impl ComponentFactory for Root {
+        fn new(_globals: &mut Globals, _cref: ComponentRef<Self>) -> Self {
+            Root
+        }
+    }
+
+    impl Component for Root {}
+
+    /// Constructed with a label as its initial properties.
+    struct Labelled(&'static str);
+
+    impl ComponentFactoryWith<&'static str> for Labelled {
+        fn new_with(
+            _globals: &mut Globals,
+            _cref: ComponentRef<Self>,
+            label: &'static str,
+        ) -> Self {
+            Labelled(label)
+        }
+    }
+
+    impl Component for Labelled {}
+
+    /// Counts the updates it receives.
+    struct Counting {
+        updates: u32,
+    }
+
+    impl ComponentFactory for Counting {
+        fn new(_globals: &mut Globals, _cref: ComponentRef<Self>) -> Self {
+            Counting { updates: 0 }
+        }
+    }
+
+    impl Component for Counting {
+        fn update(&mut self, _globals: &mut Globals) {
+            self.updates += 1;
+        }
+    }
+
+    /// Requests an update of itself from every hook, counting how many updates it receives.
+    struct SelfUpdating {
+        cref: ComponentRef<SelfUpdating>,
+        updates: u32,
+    }
+
+    impl ComponentFactory for SelfUpdating {
+        fn new(_globals: &mut Globals, cref: ComponentRef<Self>) -> Self {
+            SelfUpdating { cref, updates: 0 }
+        }
+    }
+
+    impl Component for SelfUpdating {
+        fn update(&mut self, globals: &mut Globals) {
+            self.updates += 1;
+            if self.updates == 1 {
+                globals.update(self.cref, Repaint::No, Propagate::No);
+            }
+        }
+
+        fn mounted(&mut self, globals: &mut Globals) {
+            globals.update(self.cref, Repaint::No, Propagate::No);
+        }
+
+        fn handle_message(&mut self, globals: &mut Globals, _message: &dyn Any) {
+            globals.update(self.cref, Repaint::No, Propagate::No);
+        }
+    }
+
+    fn globals() -> (Globals, ComponentRef<Root>) {
+        Globals::new(MockTheme)
+    }
+
+    fn children(globals: &Globals, cref: impl CRef) -> Vec<u64> {
+        globals
+            .untyped_internal_node(&cref)
+            .children()
+            .iter()
+            .map(|x| x.0)
+            .collect()
+    }
+
+    #[test]
+    fn stale_refs_are_rejected() {
+        let (mut globals, root) = globals();
+        let old: ComponentRef<Root> = globals.child(root);
+        globals.unmount(old);
+        let new: ComponentRef<Root> = globals.child(root);
+
+        assert!(!globals.is_valid(old));
+        assert!(globals.is_valid(new));
+        assert_eq!(children(&globals, root), vec![new.0]);
+    }
+
+    #[test]
+    fn child_with_passes_props() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<Labelled> = globals.child_with(root, "label");
+        assert_eq!(globals.get(cref).0, "label");
+    }
+
+    #[test]
+    fn validate_reports_dangling_children() {
+        let (mut globals, root) = globals();
+        let child: ComponentRef<Root> = globals.child(root);
+        assert_eq!(globals.validate(), Ok(()));
+
+        globals.map.remove(child.0);
+        assert_eq!(
+            globals.validate(),
+            Err(vec![Violation::DanglingChild {
+                parent: UntypedComponentRef(root.0),
+                parent_type: std::any::type_name::<Root>(),
+                child: UntypedComponentRef(child.0),
+            }])
+        );
+    }
+
+    #[test]
+    fn typed_conversion_checks_type() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<Labelled> = globals.child_with(root, "label");
+        let untyped = UntypedComponentRef(cref.0);
+
+        assert_eq!(untyped.try_to_typed::<Labelled>(&globals), Some(cref));
+        assert_eq!(untyped.try_typed::<Labelled>(&globals), Some(cref));
+        assert_eq!(untyped.try_to_typed::<Root>(&globals), None);
+        assert_eq!(
+            globals.type_name_of(cref),
+            Some(std::any::type_name::<Labelled>())
+        );
+
+        globals.unmount(cref);
+        assert_eq!(untyped.try_to_typed::<Labelled>(&globals), None);
+        assert_eq!(globals.type_name_of(cref), None);
+    }
+
+    #[test]
+    fn children_can_be_reordered() {
+        let (mut globals, root) = globals();
+        let a: ComponentRef<Root> = globals.child(root);
+        let b: ComponentRef<Root> = globals.child(root);
+        let c: ComponentRef<Root> = globals.child(root);
+
+        globals.move_child(root, 0, 2);
+        assert_eq!(children(&globals, root), vec![b.0, c.0, a.0]);
+
+        let order = [c, a, b].iter().map(|x| UntypedComponentRef(x.0)).collect();
+        globals.set_child_order(root, order);
+        assert_eq!(children(&globals, root), vec![c.0, a.0, b.0]);
+    }
+
+    #[test]
+    fn children_are_inserted_in_order() {
+        let (mut globals, root) = globals();
+        let b: ComponentRef<Root> = globals.child(root);
+        let d: ComponentRef<Root> = globals.child(root);
+        let a: ComponentRef<Root> = globals.child_at(root, 0);
+        let c: ComponentRef<Root> = globals.insert_before(d);
+        let e: ComponentRef<Root> = globals.insert_after(d);
+
+        assert_eq!(children(&globals, root), vec![a.0, b.0, c.0, d.0, e.0]);
+    }
+
+    #[test]
+    fn traversals_visit_in_order() {
+        let (mut globals, root) = globals();
+        let a: ComponentRef<Root> = globals.child(root);
+        let b: ComponentRef<Root> = globals.child(a);
+        let c: ComponentRef<Root> = globals.child(root);
+
+        let mut pre = Vec::new();
+        globals.visit(root, |cref, _| pre.push(cref.0));
+        assert_eq!(pre, vec![root.0, a.0, b.0, c.0]);
+
+        let mut post = Vec::new();
+        globals.visit_post_order(root, |cref, _| post.push(cref.0));
+        assert_eq!(post, vec![b.0, a.0, c.0, root.0]);
+
+        let descendants: Vec<_> = globals.descendants(a).map(|x| x.0).collect();
+        assert_eq!(descendants, vec![b.0]);
+    }
+
+    #[test]
+    fn components_can_be_found_by_type() {
+        let (mut globals, root) = globals();
+        let first: ComponentRef<Labelled> = globals.child_with(root, "first");
+        let parent: ComponentRef<Root> = globals.child(root);
+        let second: ComponentRef<Labelled> = globals.child_with(parent, "second");
+
+        let mut all = globals.find_all::<Labelled>();
+        all.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(all, expected);
+
+        assert_eq!(globals.find_first::<Labelled>(root), Some(first));
+        assert_eq!(globals.find_first::<Labelled>(parent), Some(second));
+        assert_eq!(globals.find_first::<Root>(second), None);
+    }
+
+    #[test]
+    fn try_variants_report_errors() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<Root> = globals.child(root);
+        globals.unmount(cref);
+        assert!(globals.try_get(cref).is_none());
+        assert_eq!(
+            globals.try_update(cref, Repaint::No, Propagate::No),
+            Err(CoreError::InvalidReference(UntypedComponentRef(cref.0)))
+        );
+
+        let sref = globals.signal::<()>();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = log.clone();
+        globals.listen(sref, root, move |globals, _| {
+            sink.borrow_mut().push(globals.try_emit(sref, &()))
+        });
+        globals.emit(sref, &());
+        assert_eq!(*log.borrow(), vec![Err(CoreError::SignalInUse(sref.0))]);
+    }
+
+    #[test]
+    fn weak_refs_upgrade_until_unmounted() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<Root> = globals.child(root);
+        let weak = cref.downgrade();
+        assert_eq!(globals.upgrade(weak), Some(cref));
+
+        globals.unmount(cref);
+        assert_eq!(globals.upgrade(weak), None);
+    }
+
+    #[test]
+    fn nearest_provided_value_is_consumed() {
+        let (mut globals, root) = globals();
+        let parent: ComponentRef<Root> = globals.child(root);
+        let child: ComponentRef<Root> = globals.child(parent);
+        assert_eq!(globals.consume::<u32>(child), None);
+
+        globals.provide(root, 1u32);
+        globals.provide(parent, 2u32);
+        assert_eq!(globals.consume::<u32>(child), Some(&2));
+        assert_eq!(globals.consume::<u32>(root), Some(&1));
+        assert_eq!(globals.provide(parent, 3u32), Some(2));
+    }
+
+    #[test]
+    fn metadata_is_stored_per_node() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<Root> = globals.child(root);
+        assert!(globals.set_meta(cref, "key", Box::new(1u32)).is_none());
+        assert!(globals.get_meta(root, "key").is_none());
+        assert_eq!(
+            globals
+                .get_meta(cref, "key")
+                .and_then(|x| x.downcast_ref::<u32>()),
+            Some(&1)
+        );
+
+        *globals
+            .get_meta_mut(cref, "key")
+            .and_then(|x| x.downcast_mut::<u32>())
+            .unwrap() = 2;
+        let removed = globals.remove_meta(cref, "key").unwrap();
+        assert_eq!(removed.downcast_ref::<u32>(), Some(&2));
+        assert!(globals.get_meta(cref, "key").is_none());
+    }
+
+    #[test]
+    fn roots_are_independent() {
+        let (mut globals, root) = globals();
+        let other: ComponentRef<Root> = globals.new_root();
+        let child: ComponentRef<Root> = globals.child(other);
+
+        let mut roots = globals.roots();
+        roots.sort();
+        let mut expected = vec![root.into(), other.into()];
+        expected.sort();
+        assert_eq!(roots, expected);
+        assert_eq!(globals.untyped_internal_node(&child).parent(), other.into());
+        assert_eq!(globals.validate(), Ok(()));
+    }
+
+    #[test]
+    fn detach_attach_round_trip() {
+        let (mut globals, root) = globals();
+        let moved: ComponentRef<Root> = globals.child(root);
+        let grandchild: ComponentRef<Root> = globals.child(moved);
+        let target: ComponentRef<Root> = globals.child(root);
+        let sref = globals.component_signal::<()>(moved);
+
+        let subtree = globals.detach(moved);
+        assert_eq!(subtree.root(), moved.into());
+        assert!(globals.is_valid(moved) && globals.is_valid(grandchild));
+        assert_eq!(children(&globals, root), vec![target.0]);
+        assert_eq!(globals.validate(), Ok(()));
+
+        assert_eq!(globals.attach(subtree, target), moved.into());
+        assert_eq!(
+            globals.untyped_internal_node(&moved).parent(),
+            target.into()
+        );
+        assert_eq!(children(&globals, moved), vec![grandchild.0]);
+        assert!(globals.try_listen(sref, root, |_, _| {}).is_ok());
+        assert_eq!(globals.validate(), Ok(()));
+    }
+
+    #[test]
+    fn frozen_subtrees_skip_propagated_updates() {
+        let (mut globals, root) = globals();
+        let panel: ComponentRef<Root> = globals.child(root);
+        let inner: ComponentRef<Counting> = globals.child(panel);
+        globals.get_mut(inner).updates = 0;
+
+        globals.set_frozen(panel, true);
+        assert!(globals.is_frozen(inner));
+        globals.update(root, Repaint::No, Propagate::Yes);
+        assert_eq!(globals.get(inner).updates, 0);
+
+        globals.set_frozen(panel, false);
+        globals.update(root, Repaint::No, Propagate::Yes);
+        assert_eq!(globals.get(inner).updates, 1);
+    }
+
+    #[test]
+    fn signals_can_be_mapped_and_filtered() {
+        let (mut globals, root) = globals();
+        let src = globals.signal::<u32>();
+        let doubled = globals.map_signal(src, |x| x * 2);
+        let even = globals.filter_signal(src, |x| x % 2 == 0);
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let sink = log.clone();
+        globals.listen(doubled, root, move |_, &x| {
+            sink.borrow_mut().push(("doubled", x))
+        });
+        let sink = log.clone();
+        globals.listen(even, root, move |_, &x| sink.borrow_mut().push(("even", x)));
+
+        globals.emit(src, &1);
+        globals.emit(src, &2);
+        assert_eq!(
+            *log.borrow(),
+            vec![("doubled", 2), ("doubled", 4), ("even", 2)]
+        );
+    }
+
+    #[test]
+    fn emit_deferred_runs_after_emission_in_order() {
+        let (mut globals, root) = globals();
+        let sref = globals.signal::<u32>();
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let sink = log.clone();
+        globals.listen_with_priority(sref, root, 1, move |globals, &event| {
+            sink.borrow_mut().push(("first", event));
+            if event == 0 {
+                globals.emit_deferred(sref, 1);
+                globals.emit_deferred(sref, 2);
+            }
+        });
+        let sink = log.clone();
+        globals.listen(sref, root, move |_, &event| {
+            sink.borrow_mut().push(("second", event))
+        });
+
+        globals.emit(sref, &0);
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                ("first", 0),
+                ("second", 0),
+                ("first", 1),
+                ("second", 1),
+                ("first", 2),
+                ("second", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn fnmut_listeners_keep_state() {
+        let (mut globals, root) = globals();
+        let sref = globals.signal::<()>();
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let sink = log.clone();
+        let mut count = 0;
+        globals.listen_mut(sref, root, move |_, _| {
+            count += 1;
+            sink.borrow_mut().push(count);
+        });
+
+        globals.emit(sref, &());
+        globals.emit(sref, &());
+        assert_eq!(*log.borrow(), vec![1, 2]);
+    }
+
+    #[test]
+    fn self_update_from_mounted_runs() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<SelfUpdating> = globals.child(root);
+        // the update from `mounted` requests another one from within `update`
+        assert_eq!(globals.get(cref).updates, 2);
+    }
+
+    #[test]
+    fn self_update_from_update_runs_after_pass() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<SelfUpdating> = globals.child(root);
+        globals.get_mut(cref).updates = 0;
+        globals.update(cref, Repaint::No, Propagate::No);
+        assert_eq!(globals.get(cref).updates, 2);
+    }
+
+    #[test]
+    fn self_update_from_send_runs() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<SelfUpdating> = globals.child(root);
+        globals.get_mut(cref).updates = 5;
+        globals.send(cref, &());
+        assert_eq!(globals.get(cref).updates, 6);
+        assert!(globals.is_available(cref));
+    }
+
+    #[test]
+    fn child_signals_cover_batches_and_replacement() {
+        let (mut globals, root) = globals();
+        let parent: ComponentRef<Root> = globals.child(root);
+        let added = globals.on_child_added(parent);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = log.clone();
+        globals.listen(added, root, move |_, &child| sink.borrow_mut().push(child));
+
+        let first: ComponentRef<Root> = globals.child(parent);
+        let batch: Vec<ComponentRef<Labelled>> =
+            globals.children_from_iter(parent, vec!["a", "b", "c"]);
+        let mut expected = vec![first.into()];
+        expected.extend(batch.into_iter().map(UntypedComponentRef::from));
+        assert_eq!(*log.borrow(), expected);
+
+        let parent: ComponentRef<SelfUpdating> = globals.replace_component(parent);
+        let added = globals.on_child_added(parent);
+        assert!(globals.try_listen(added, root, |_, _| {}).is_ok());
+    }
+
+    #[test]
+    fn replacement_reports_unmount_then_mount() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<Root> = globals.child(root);
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let sink = log.clone();
+        globals.listen(globals.on_component_unmounted, root, move |_, event| {
+            sink.borrow_mut().push(("unmounted", event.type_name))
+        });
+        let sink = log.clone();
+        globals.listen(globals.on_component_mounted, root, move |_, event| {
+            sink.borrow_mut().push(("mounted", event.type_name))
+        });
+
+        let _: ComponentRef<SelfUpdating> = globals.replace_component(cref);
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                ("unmounted", std::any::type_name::<Root>()),
+                ("mounted", std::any::type_name::<SelfUpdating>()),
+            ]
+        );
+    }
+
+    #[test]
+    fn emit_until_handled_stops_at_handler() {
+        let (mut globals, root) = globals();
+        let sref = globals.signal::<u32>();
+        let inner = globals.signal::<()>();
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        globals.listen(inner, root, |globals, _| globals.mark_handled());
+        for (name, priority) in [("first", 2), ("second", 1), ("third", 0)] {
+            let sink = log.clone();
+            globals.listen_with_priority(sref, root, priority, move |globals, event| {
+                sink.borrow_mut().push(name);
+                // marking a nested emission must not consume this one
+                globals.emit(inner, &());
+                if name == "second" && *event == 1 {
+                    globals.mark_handled();
+                }
+            });
+        }
+
+        assert_eq!(globals.emit_until_handled(sref, &1), EmitResult::Handled);
+        assert_eq!(*log.borrow(), vec!["first", "second"]);
+
+        log.borrow_mut().clear();
+        assert_eq!(globals.emit_until_handled(sref, &0), EmitResult::Unhandled);
+        assert_eq!(*log.borrow(), vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn shutdown_unmounts_children_first() {
+        let (mut globals, root) = globals();
+        let parent: ComponentRef<Root> = globals.child(root);
+        let child: ComponentRef<SelfUpdating> = globals.child(parent);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = log.clone();
+        globals.listen(globals.on_component_unmounted, root, move |_, event| {
+            sink.borrow_mut().push(event.cref)
+        });
+
+        assert!(globals.shutdown().is_clean());
+        assert_eq!(*log.borrow(), vec![child.into(), parent.into()]);
+    }
+
+    #[test]
+    fn nested_set_value_wins() {
+        let (mut globals, root) = globals();
+        let prop = globals.property(0u32);
+        globals.listen(prop.on_changed, root, move |globals, &value| {
+            assert_eq!(*globals.value(prop), value);
+            if value == 1 {
+                globals.set_value(prop, 2);
+            }
+        });
+
+        globals.set_value(prop, 1);
+        assert_eq!(*globals.value(prop), 2);
+
+        globals.remove_property(prop);
+        assert!(globals.shutdown().is_clean());
+    }
+
+    #[test]
+    fn names_are_removed_with_their_component() {
+        let (mut globals, root) = globals();
+        let a: ComponentRef<Root> = globals.child(root);
+        let b: ComponentRef<Root> = globals.child(root);
+        globals.register_name(a, "first");
+        globals.register_name(a, "second");
+        globals.register_name(b, "first");
+
+        globals.unmount(a);
+        assert_eq!(globals.lookup_name::<Root>("first"), Some(b));
+        assert_eq!(globals.lookup_name::<Root>("second"), None);
+
+        globals.unmount(b);
+        assert_eq!(globals.lookup_name::<Root>("first"), None);
+        assert!(globals.node_names.is_empty());
+    }
+
+    #[test]
+    fn pool_forgets_reused_and_unmounted_components() {
+        let (mut globals, root) = globals();
+        let a: ComponentRef<Root> = globals.child(root);
+        let b: ComponentRef<Root> = globals.child(root);
+        globals.detach_to_pool(a);
+        globals.detach_to_pool(b);
+
+        globals.unmount(a);
+        assert_eq!(globals.pooled_count::<Root>(), 1);
+        assert_eq!(globals.attach_from_pool::<Root>(root), Some(b));
+        assert_eq!(globals.pooled_count::<Root>(), 0);
+        assert!(globals.pooled.is_empty());
+    }
+
+    #[test]
+    fn unmounted_components_are_skipped_by_run_frame() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<Root> = globals.child(root);
+        globals.schedule(cref, Phase::Layout);
+        globals.unmount(cref);
+        globals.run_frame();
+        assert!(globals.scheduled.is_empty());
+    }
+
+    #[test]
+    fn clearing_undo_history_ends_transactions() {
+        struct Noop;
+
+        impl commands::Command for Noop {
+            fn apply(&mut self, _globals: &mut Globals) {}
+            fn undo(&mut self, _globals: &mut Globals) {}
+        }
+
+        let (mut globals, _) = globals();
+        globals.begin_transaction();
+        globals.execute(Noop);
+        globals.clear_undo_history();
+        assert!(!globals.undo_stack().in_transaction());
+
+        globals.execute(Noop);
+        assert!(globals.undo_stack().can_undo());
+    }
+
+    #[test]
+    fn raise_and_lower_saturate() {
+        let (mut globals, root) = globals();
+        let a: ComponentRef<Root> = globals.child(root);
+        let b: ComponentRef<Root> = globals.child(root);
+
+        globals.set_z_index(b, i32::MAX);
+        globals.raise(a);
+        assert_eq!(globals.z_index(a), i32::MAX);
+
+        globals.set_z_index(b, i32::MIN);
+        globals.lower(a);
+        assert_eq!(globals.z_index(a), i32::MIN);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn emits_from_update_are_reported() {
+        /// Emits `on_update` from its update, either directly or through a listener of `on_announce`.
+        struct Emitting {
+            on_update: SignalRef<()>,
+            direct: bool,
+        }
+
+        impl ComponentFactoryWith<bool> for Emitting {
+            fn new_with(globals: &mut Globals, cref: ComponentRef<Self>, direct: bool) -> Self {
+                let on_update = globals.component_signal(cref);
+                Emitting { on_update, direct }
+            }
+        }
+
+        impl Component for Emitting {
+            fn update(&mut self, globals: &mut Globals) {
+                if self.direct {
+                    globals.emit(self.on_update, &());
+                } else {
+                    globals.announce("updated", Politeness::Polite);
+                }
+            }
+        }
+
+        let (mut globals, root) = globals();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = log.clone();
+        globals.listen(globals.on_component_panic, root, move |_, panic| {
+            sink.borrow_mut().push(panic.type_name)
+        });
+
+        let relayed: ComponentRef<Emitting> = globals.child_with(root, false);
+        let on_update = globals.get(relayed).on_update;
+        globals.listen(globals.on_announce, root, move |globals, _| {
+            globals.emit(on_update, &())
+        });
+        globals.update(relayed, Repaint::No, Propagate::No);
+        assert!(log.borrow().is_empty());
+
+        let direct: ComponentRef<Emitting> = globals.child_with(root, true);
+        globals.update(direct, Repaint::No, Propagate::No);
+        assert_eq!(*log.borrow(), vec![std::any::type_name::<Emitting>()]);
+    }
+
+    #[test]
+    fn owned_signals_are_removed_with_their_listeners() {
+        let (mut globals, root) = globals();
+        let owner: ComponentRef<Root> = globals.child(root);
+        let listener: ComponentRef<Root> = globals.child(root);
+        let sref = globals.component_signal::<()>(owner);
+        globals.listen(sref, listener, |_, _| {});
+        globals.listen(sref, root, |_, _| {});
+
+        globals.unmount(listener);
+        assert_eq!(globals.untyped_internal_node(&root).listener_count(), 1);
+
+        globals.unmount(owner);
+        assert_eq!(globals.untyped_internal_node(&root).listener_count(), 0);
+        assert!(globals.listening.is_empty());
+    }
+
+    #[test]
+    fn handlers_skip_components_in_use() {
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<Counting> = globals.child(root);
+        let sref = globals.signal::<()>();
+        let prop = globals.property(0u32);
+        globals.connect(sref, cref, |counting, _, _| counting.updates += 10);
+        globals.bind(prop, cref, |counting, &value| counting.updates += value);
+        let updates = globals.get(cref).updates;
+
+        globals.with(cref, |_, globals| {
+            globals.emit(sref, &());
+            globals.set_value(prop, 100);
+        });
+        assert!(!globals.is_poisoned(cref));
+        assert_eq!(globals.get(cref).updates, updates);
+
+        globals.emit(sref, &());
+        assert_eq!(globals.get(cref).updates, updates + 10);
+    }
+
+    #[test]
+    fn nested_set_value_reaches_every_listener() {
+        let (mut globals, root) = globals();
+        let prop = globals.property("initial");
+        globals.listen(prop.on_changed, root, move |globals, &value| {
+            if value == "first" {
+                globals.set_value(prop, "second");
+            }
+        });
+        let cref: ComponentRef<Labelled> = globals.child_with(root, "");
+        globals.bind(prop, cref, |labelled, &value| labelled.0 = value);
+
+        globals.set_value(prop, "first");
+        assert_eq!(globals.get(cref).0, "second");
+    }
+
+    #[test]
+    fn render_reuses_cached_display_commands() {
+        /// Counts how many times it was asked for its display commands.
+        struct Painted {
+            displays: u32,
+        }
+
+        impl ComponentFactory for Painted {
+            fn new(_globals: &mut Globals, _cref: ComponentRef<Self>) -> Self {
+                Painted { displays: 0 }
+            }
+        }
+
+        impl Component for Painted {
+            fn display(&mut self) -> Vec<gfx::DisplayCommand> {
+                self.displays += 1;
+                vec![gfx::DisplayCommand::Clear(gfx::Color::new(
+                    0.0, 0.0, 0.0, 1.0,
+                ))]
+            }
+        }
+
+        let (mut globals, root) = globals();
+        let cref: ComponentRef<Painted> = globals.child(root);
+        let cmds = crate::test::snapshot(&globals.render(root));
+        assert_eq!(crate::test::snapshot(&globals.render(root)), cmds);
+        assert_eq!(globals.get(cref).displays, 1);
+
+        globals.update(cref, Repaint::No, Propagate::No);
+        globals.render(root);
+        assert_eq!(globals.get(cref).displays, 1);
+
+        globals.update(cref, Repaint::Yes, Propagate::No);
+        assert_eq!(crate::test::snapshot(&globals.render(root)), cmds);
+        assert_eq!(globals.get(cref).displays, 2);
+    }
+
+    #[test]
+    fn memory_report_counts_cached_display_commands() {
+        let (mut globals, root) = globals();
+        let _: ComponentRef<crate::kit::Spinner> = globals.child(root);
+        assert_eq!(globals.memory_report().display_bytes, 0);
+
+        // the mock theme paints a `Save`, `Clear` and `Restore` for the spinner
+        globals.render(root);
+        assert_eq!(
+            globals.memory_report().display_bytes,
+            3 * std::mem::size_of::<gfx::DisplayCommand>()
+        );
     }
 }
@@ -1,27 +1,104 @@
 use {
-    crate::{core, signal, theme},
+    crate::{core, kit, theme},
     reclutch::display as gfx,
 };
 
 pub type ButtonRef = core::ComponentRef<Button>;
 
 pub struct Button {
-    pub on_click: core::SignalRef<()>,
+    pub on_click: core::SignalRef<kit::ClickEvent>,
+    spinner: Option<kit::SpinnerRef>,
     painter: theme::Painter<Self>,
+    cref: ButtonRef,
 }
 
 impl core::ComponentFactory for Button {
-    fn new(globals: &mut core::Globals, _cref: core::ComponentRef<Self>) -> Self {
+    fn new(globals: &mut core::Globals, cref: core::ComponentRef<Self>) -> Self {
         Button {
-            on_click: globals.signal(),
+            on_click: globals.component_signal(cref),
+            spinner: None,
             painter: globals.painter(theme::painters::BUTTON),
+            cref,
         }
     }
 }
 
+/// Initial state of a [`Button`](Button), for use with [`Globals::child_with`](core::Globals::child_with).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ButtonProps {
+    pub loading: bool,
+    pub tooltip: Option<String>,
+}
+
+impl core::ComponentFactoryWith<ButtonProps> for Button {
+    fn new_with(
+        globals: &mut core::Globals,
+        cref: core::ComponentRef<Self>,
+        props: ButtonProps,
+    ) -> Self {
+        let mut button: Button = core::ComponentFactory::new(globals, cref);
+        if props.loading {
+            button.spinner = Some(globals.child(cref));
+        }
+        if let Some(tooltip) = props.tooltip {
+            globals.set_prop(cref, core::TOOLTIP, tooltip);
+        }
+        button
+    }
+}
+
 impl core::Component for Button {
     #[inline]
     fn display(&mut self) -> Vec<gfx::DisplayCommand> {
         theme::paint(self, |o| &mut o.painter)
     }
 }
+
+impl Button {
+    /// Puts the button into (or out of) the loading state.
+    ///
+    /// While loading, the button hosts a [`Spinner`](kit::Spinner) child.
+    pub fn set_loading(&mut self, globals: &mut core::Globals, loading: bool) {
+        if loading == self.spinner.is_some() {
+            return;
+        }
+
+        if loading {
+            self.spinner = Some(globals.child(self.cref));
+        } else if let Some(spinner) = self.spinner.take() {
+            globals.unmount(spinner);
+        }
+
+        globals.update(self.cref, core::Repaint::Yes, core::Propagate::No);
+    }
+
+    #[inline]
+    pub fn is_loading(&self) -> bool {
+        self.spinner.is_some()
+    }
+
+    /// Returns the spinner shown while loading, if any.
+    #[inline]
+    pub fn spinner(&self) -> Option<kit::SpinnerRef> {
+        self.spinner
+    }
+
+    /// Sets (or clears) the tooltip describing what the button does.
+    ///
+    /// The tooltip is stored on the node as the [`TOOLTIP`](core::TOOLTIP) property.
+    pub fn set_tooltip(&self, globals: &mut core::Globals, tooltip: Option<String>) {
+        match tooltip {
+            Some(tooltip) => globals.set_prop(self.cref, core::TOOLTIP, tooltip),
+            None => {
+                globals.remove_prop(self.cref, core::TOOLTIP);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn tooltip<'a>(&self, globals: &'a core::Globals) -> Option<&'a str> {
+        globals
+            .get_prop(self.cref, core::TOOLTIP)
+            .map(String::as_str)
+    }
+}
@@ -0,0 +1,41 @@
+use {crate::core, std::time::Instant};
+
+/// Drives an animation from [`Globals::run_frame`](core::Globals::run_frame), measuring the time between frames.
+///
+/// The component is scheduled for the layout phase of every frame, so that it advances before anything is painted.
+#[derive(Debug, Default)]
+pub(crate) struct FrameClock {
+    last: Option<Instant>,
+}
+
+impl FrameClock {
+    /// Starts ticking on the next frame; typically called from [`mounted`](core::Component::mounted).
+    pub(crate) fn start(&mut self, globals: &mut core::Globals, cref: impl core::CRef) {
+        self.last = None;
+        globals.schedule(cref, core::Phase::Layout);
+    }
+
+    /// Forwarded every [`run_phase`](core::Component::run_phase), returning the time elapsed since the previous frame whenever it's time to advance.
+    pub(crate) fn tick(
+        &mut self,
+        globals: &mut core::Globals,
+        cref: impl core::CRef,
+        phase: core::Phase,
+    ) -> Option<std::time::Duration> {
+        match phase {
+            core::Phase::Layout => {
+                // rescheduling for layout from here would run again within this frame, so it's done after the frame instead
+                globals.schedule(cref, core::Phase::PostFrame);
+                let now = Instant::now();
+                let dt = self.last.map(|last| now - last).unwrap_or_default();
+                self.last = Some(now);
+                Some(dt)
+            }
+            core::Phase::PostFrame => {
+                globals.schedule(cref, core::Phase::Layout);
+                None
+            }
+            core::Phase::Paint => None,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use reclutch::display as gfx;
+
+/// A button on a pointing device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+impl Default for MouseButton {
+    fn default() -> Self {
+        MouseButton::Left
+    }
+}
+
+/// Modifier keys held at the time of an interaction.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    /// The Windows/Command/Super key.
+    pub logo: bool,
+}
+
+/// Payload of click signals, such as [`Button::on_click`](crate::kit::Button::on_click).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ClickEvent {
+    pub button: MouseButton,
+    pub modifiers: Modifiers,
+    /// Position of the pointer, relative to the clicked component.
+    pub position: gfx::Point,
+}
+
+/// Payload of signals reporting that a value changed, carrying both the previous and the current value.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChangeEvent<T> {
+    pub old: T,
+    pub new: T,
+}
@@ -0,0 +1,130 @@
+use {
+    crate::{core, kit, theme},
+    reclutch::display as gfx,
+    std::time::Duration,
+};
+
+pub type FramesRef = core::ComponentRef<Frames>;
+
+/// Plays back a sequence of image frames at a fixed rate.
+///
+/// Like [`Spinner`](crate::kit::Spinner), playback is driven by [`Globals::run_frame`](core::Globals::run_frame) once mounted.
+pub struct Frames {
+    frames: Vec<gfx::ResourceReference>,
+    fps: f32,
+    current: usize,
+    elapsed: f32,
+    clock: kit::FrameClock,
+    painter: theme::Painter<Self>,
+    cref: FramesRef,
+}
+
+impl core::ComponentFactory for Frames {
+    fn new(globals: &mut core::Globals, cref: core::ComponentRef<Self>) -> Self {
+        Frames {
+            frames: Vec::new(),
+            fps: 24.0,
+            current: 0,
+            elapsed: 0.0,
+            clock: Default::default(),
+            painter: globals.painter(theme::painters::FRAMES),
+            cref,
+        }
+    }
+}
+
+impl core::Component for Frames {
+    #[inline]
+    fn display(&mut self) -> Vec<gfx::DisplayCommand> {
+        theme::paint(self, |o| &mut o.painter)
+    }
+
+    fn mounted(&mut self, globals: &mut core::Globals) {
+        self.clock.start(globals, self.cref);
+    }
+
+    fn run_phase(&mut self, globals: &mut core::Globals, phase: core::Phase) {
+        if let Some(dt) = self.clock.tick(globals, self.cref, phase) {
+            self.advance(globals, dt);
+        }
+    }
+}
+
+impl Frames {
+    /// Advances playback by the time elapsed since the last frame, wrapping around at the end of the sequence.
+    ///
+    /// Only schedules a repaint if the visible frame changed. Playback holds still while [reduced motion](core::AccessibilityPrefs::reduce_motion) is requested.
+    pub fn advance(&mut self, globals: &mut core::Globals, dt: Duration) {
+        if self.frames.is_empty() || self.fps <= 0.0 || globals.accessibility_prefs().reduce_motion
+        {
+            return;
+        }
+
+        let frame_time = 1.0 / self.fps;
+        self.elapsed += dt.as_secs_f32();
+        let skipped = (self.elapsed / frame_time) as usize;
+        if skipped == 0 {
+            return;
+        }
+
+        self.elapsed -= skipped as f32 * frame_time;
+        self.current = (self.current + skipped) % self.frames.len();
+        globals.update(self.cref, core::Repaint::Yes, core::Propagate::No);
+    }
+
+    /// Replaces the frame sequence and restarts playback.
+    pub fn set_frames(&mut self, globals: &mut core::Globals, frames: Vec<gfx::ResourceReference>) {
+        self.frames = frames;
+        self.current = 0;
+        self.elapsed = 0.0;
+        globals.update(self.cref, core::Repaint::Yes, core::Propagate::No);
+    }
+
+    /// Sets the playback rate, in frames per second.
+    #[inline]
+    pub fn set_fps(&mut self, fps: f32) {
+        self.fps = fps;
+    }
+
+    #[inline]
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    #[inline]
+    pub fn frames(&self) -> &[gfx::ResourceReference] {
+        &self.frames
+    }
+
+    /// Returns the index of the frame currently shown.
+    #[inline]
+    pub fn frame_index(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the frame currently shown, or `None` if there are no frames.
+    #[inline]
+    pub fn current_frame(&self) -> Option<gfx::ResourceReference> {
+        self.frames.get(self.current).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test::MockTheme};
+
+    #[test]
+    fn playback_is_driven_by_run_frame() {
+        let (mut globals, frames): (_, FramesRef) = core::Globals::new(MockTheme);
+        globals.with(frames, |frames, globals| {
+            let images = (0..1000).map(gfx::ResourceReference::Image).collect();
+            frames.set_frames(globals, images);
+            frames.set_fps(1000.0);
+        });
+
+        globals.run_frame();
+        std::thread::sleep(Duration::from_millis(10));
+        globals.run_frame();
+        assert!(globals.get(frames).frame_index() > 0);
+    }
+}
@@ -21,6 +21,15 @@ impl core::ComponentFactory for Label {
     }
 }
 
+impl<S: Into<gfx::DisplayText>> core::ComponentFactoryWith<S> for Label {
+    fn new_with(globals: &mut core::Globals, cref: core::ComponentRef<Self>, text: S) -> Self {
+        Label {
+            text: text.into(),
+            ..core::ComponentFactory::new(globals, cref)
+        }
+    }
+}
+
 impl core::Component for Label {
     #[inline]
     fn display(&mut self) -> Vec<gfx::DisplayCommand> {
@@ -34,6 +43,15 @@ impl Label {
         globals.update(self.cref, core::Repaint::Yes, core::Propagate::No);
     }
 
+    /// Keeps the text of a label in sync with a property.
+    pub fn bind_text<T: Clone + Into<gfx::DisplayText> + 'static>(
+        globals: &mut core::Globals,
+        cref: LabelRef,
+        prop: core::PropertyRef<T>,
+    ) {
+        globals.bind(prop, cref, |label, text| label.text = text.clone().into());
+    }
+
     #[inline]
     pub fn text(&self) -> gfx::DisplayText {
         self.text.clone()
@@ -1,4 +1,12 @@
 pub mod button;
+mod clock;
+pub mod event;
+pub mod frames;
 pub mod label;
+pub mod selection;
+pub mod spinner;
+pub mod wizard;
 
-pub use {button::*, label::*};
+pub use {button::*, event::*, frames::*, label::*, selection::*, spinner::*, wizard::*};
+
+pub(crate) use clock::FrameClock;
@@ -0,0 +1,171 @@
+use {
+    crate::{core, kit},
+    std::collections::BTreeSet,
+};
+
+/// How many items a [`SelectionModel`](SelectionModel) allows to be selected at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SelectionMode {
+    Single,
+    Multiple,
+}
+
+impl Default for SelectionMode {
+    fn default() -> Self {
+        SelectionMode::Single
+    }
+}
+
+/// The kind of selection requested by the user, usually derived from the modifier keys held during a click.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SelectionGesture {
+    /// Plain click; selects only the item.
+    Replace,
+    /// Ctrl-click; toggles the item, keeping the rest of the selection.
+    Toggle,
+    /// Shift-click; selects every item between the anchor and the item.
+    Range,
+}
+
+/// Selection state shared by list-like widgets, so that selection behaves consistently across them.
+///
+/// Items are identified by index. In [`Single`](SelectionMode::Single) mode, every gesture behaves like [`Replace`](SelectionGesture::Replace).
+pub struct SelectionModel {
+    /// Emitted with the previously and newly selected indices (in ascending order) whenever the selection changes.
+    pub on_changed: core::SignalRef<kit::ChangeEvent<Vec<usize>>>,
+    mode: SelectionMode,
+    selected: BTreeSet<usize>,
+    anchor: Option<usize>,
+}
+
+impl SelectionModel {
+    /// Creates an empty selection for the widget `owner`, whose unmounting removes [`on_changed`](SelectionModel::on_changed).
+    pub fn new(globals: &mut core::Globals, owner: impl core::CRef, mode: SelectionMode) -> Self {
+        SelectionModel {
+            on_changed: globals.component_signal(owner),
+            mode,
+            selected: BTreeSet::new(),
+            anchor: None,
+        }
+    }
+
+    /// Applies a selection gesture to the item at `index`.
+    pub fn select(&mut self, globals: &mut core::Globals, index: usize, gesture: SelectionGesture) {
+        let gesture = match self.mode {
+            SelectionMode::Single => SelectionGesture::Replace,
+            SelectionMode::Multiple => gesture,
+        };
+
+        let mut selected = BTreeSet::new();
+        match (gesture, self.anchor) {
+            (SelectionGesture::Range, Some(anchor)) => {
+                selected.extend(anchor.min(index)..=anchor.max(index));
+            }
+            (SelectionGesture::Toggle, _) => {
+                selected = self.selected.clone();
+                if selected.remove(&index) {
+                    // keep the anchor on a selected item, so that it can't resurrect a deselected one
+                    self.anchor = selected
+                        .iter()
+                        .copied()
+                        .min_by_key(|&x| (x as isize - index as isize).abs());
+                } else {
+                    selected.insert(index);
+                    self.anchor = Some(index);
+                }
+            }
+            _ => {
+                selected.insert(index);
+                self.anchor = Some(index);
+            }
+        }
+
+        self.set(globals, selected);
+    }
+
+    /// Selects the items `0..count`. Does nothing in [`Single`](SelectionMode::Single) mode.
+    pub fn select_all(&mut self, globals: &mut core::Globals, count: usize) {
+        if self.mode == SelectionMode::Multiple {
+            self.set(globals, (0..count).collect());
+        }
+    }
+
+    /// Deselects every item.
+    pub fn clear(&mut self, globals: &mut core::Globals) {
+        self.anchor = None;
+        self.set(globals, BTreeSet::new());
+    }
+
+    /// Changes the selection mode, keeping only the anchor selected when switching to [`Single`](SelectionMode::Single).
+    pub fn set_mode(&mut self, globals: &mut core::Globals, mode: SelectionMode) {
+        self.mode = mode;
+        if mode == SelectionMode::Single && self.selected.len() > 1 {
+            let selected = self.anchor.into_iter().collect();
+            self.set(globals, selected);
+        }
+    }
+
+    #[inline]
+    pub fn mode(&self) -> SelectionMode {
+        self.mode
+    }
+
+    #[inline]
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selected.contains(&index)
+    }
+
+    /// Returns the selected indices in ascending order.
+    #[inline]
+    pub fn selected(&self) -> impl Iterator<Item = usize> + '_ {
+        self.selected.iter().copied()
+    }
+
+    /// Returns the item from which [range selections](SelectionGesture::Range) extend.
+    #[inline]
+    pub fn anchor(&self) -> Option<usize> {
+        self.anchor
+    }
+
+    fn set(&mut self, globals: &mut core::Globals, selected: BTreeSet<usize>) {
+        if selected != self.selected {
+            let old = std::mem::replace(&mut self.selected, selected);
+            let event = kit::ChangeEvent {
+                old: old.into_iter().collect(),
+                new: self.selected().collect(),
+            };
+            globals.emit(self.on_changed, &event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{core::ComponentRef, test::MockTheme},
+    };
+
+    struct Owner;
+
+    impl core::ComponentFactory for Owner {
+        fn new(_globals: &mut core::Globals, _cref: ComponentRef<Self>) -> Self {
+            Owner
+        }
+    }
+
+    impl core::Component for Owner {}
+
+    #[test]
+    fn toggling_off_moves_anchor() {
+        let (mut globals, root): (_, ComponentRef<Owner>) = core::Globals::new(MockTheme);
+        let mut model = SelectionModel::new(&mut globals, root, SelectionMode::Multiple);
+        model.select(&mut globals, 2, SelectionGesture::Replace);
+        model.select(&mut globals, 5, SelectionGesture::Toggle);
+        model.select(&mut globals, 5, SelectionGesture::Toggle);
+        assert_eq!(model.anchor(), Some(2));
+
+        model.set_mode(&mut globals, SelectionMode::Single);
+        assert_eq!(model.selected().collect::<Vec<_>>(), vec![2]);
+    }
+}
@@ -0,0 +1,140 @@
+use {
+    crate::{core, kit, theme},
+    reclutch::display as gfx,
+    std::time::Duration,
+};
+
+pub type SpinnerRef = core::ComponentRef<Spinner>;
+
+/// Full rotations completed by a spinner every second.
+const ROTATIONS_PER_SECOND: f32 = 1.0;
+
+/// Size variant of a [`Spinner`](Spinner).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpinnerSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl Default for SpinnerSize {
+    fn default() -> Self {
+        SpinnerSize::Medium
+    }
+}
+
+/// Indeterminate loading indicator.
+///
+/// Once mounted, the spinner animates itself from [`Globals::run_frame`](core::Globals::run_frame), which should be called every frame.
+pub struct Spinner {
+    size: SpinnerSize,
+    rotation: f32,
+    clock: kit::FrameClock,
+    painter: theme::Painter<Self>,
+    cref: SpinnerRef,
+}
+
+impl core::ComponentFactory for Spinner {
+    fn new(globals: &mut core::Globals, cref: core::ComponentRef<Self>) -> Self {
+        Spinner {
+            size: Default::default(),
+            rotation: 0.0,
+            clock: Default::default(),
+            painter: globals.painter(theme::painters::SPINNER),
+            cref,
+        }
+    }
+}
+
+impl core::ComponentFactoryWith<SpinnerSize> for Spinner {
+    fn new_with(
+        globals: &mut core::Globals,
+        cref: core::ComponentRef<Self>,
+        size: SpinnerSize,
+    ) -> Self {
+        Spinner {
+            size,
+            ..core::ComponentFactory::new(globals, cref)
+        }
+    }
+}
+
+impl core::Component for Spinner {
+    #[inline]
+    fn display(&mut self) -> Vec<gfx::DisplayCommand> {
+        theme::paint(self, |o| &mut o.painter)
+    }
+
+    fn mounted(&mut self, globals: &mut core::Globals) {
+        self.clock.start(globals, self.cref);
+    }
+
+    fn run_phase(&mut self, globals: &mut core::Globals, phase: core::Phase) {
+        if let Some(dt) = self.clock.tick(globals, self.cref, phase) {
+            self.advance(globals, dt);
+        }
+    }
+}
+
+impl Spinner {
+    /// Advances the rotation by the time elapsed since the last frame.
+    ///
+    /// Does nothing if the user asked for [reduced motion](core::AccessibilityPrefs::reduce_motion).
+    pub fn advance(&mut self, globals: &mut core::Globals, dt: Duration) {
+        if globals.accessibility_prefs().reduce_motion {
+            return;
+        }
+
+        let turns = dt.as_secs_f32() * ROTATIONS_PER_SECOND;
+        self.rotation =
+            (self.rotation + turns * std::f32::consts::PI * 2.0) % (std::f32::consts::PI * 2.0);
+        globals.update(self.cref, core::Repaint::Yes, core::Propagate::No);
+    }
+
+    pub fn set_size(&mut self, globals: &mut core::Globals, size: SpinnerSize) {
+        self.size = size;
+        globals.update(self.cref, core::Repaint::Yes, core::Propagate::No);
+    }
+
+    #[inline]
+    pub fn size(&self) -> SpinnerSize {
+        self.size
+    }
+
+    /// Returns the current rotation, in radians.
+    #[inline]
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{core::ComponentRef, test::MockTheme},
+    };
+
+    struct Owner;
+
+    impl core::ComponentFactory for Owner {
+        fn new(_globals: &mut core::Globals, _cref: ComponentRef<Self>) -> Self {
+            Owner
+        }
+    }
+
+    impl core::Component for Owner {}
+
+    #[test]
+    fn loading_spinner_is_driven_by_run_frame() {
+        let (mut globals, root): (_, ComponentRef<Owner>) = core::Globals::new(MockTheme);
+        let button: kit::ButtonRef = globals.child(root);
+        globals.with(button, |button, globals| button.set_loading(globals, true));
+        let spinner = globals.get(button).spinner().unwrap();
+
+        globals.run_frame();
+        std::thread::sleep(Duration::from_millis(10));
+        globals.run_frame();
+        assert!(globals.get(spinner).rotation() > 0.0);
+    }
+}
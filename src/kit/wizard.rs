@@ -0,0 +1,198 @@
+use {
+    crate::{core, kit, theme},
+    reclutch::display as gfx,
+};
+
+pub type WizardRef = core::ComponentRef<Wizard>;
+
+/// Decides whether the user may leave a step, e.g. by checking the fields of a form.
+pub type StepValidator = Box<dyn Fn(&core::Globals) -> bool>;
+
+/// Container guiding the user through an ordered sequence of steps, each being a child subtree.
+///
+/// The progress header is drawn by the theme's [`WIZARD`](theme::painters::WIZARD) painter, from [`current`](Wizard::current) and [`len`](Wizard::len).
+pub struct Wizard {
+    /// Emitted with the indices of the previous and new step whenever the current step changes.
+    pub on_step_changed: core::SignalRef<kit::ChangeEvent<usize>>,
+    /// Emitted when the last step is completed through [`finish`](Wizard::finish).
+    pub on_finished: core::SignalRef<()>,
+    steps: Vec<(core::UntypedComponentRef, Option<StepValidator>)>,
+    current: usize,
+    painter: theme::Painter<Self>,
+    cref: WizardRef,
+}
+
+impl core::ComponentFactory for Wizard {
+    fn new(globals: &mut core::Globals, cref: core::ComponentRef<Self>) -> Self {
+        Wizard {
+            on_step_changed: globals.component_signal(cref),
+            on_finished: globals.component_signal(cref),
+            steps: Vec::new(),
+            current: 0,
+            painter: globals.painter(theme::painters::WIZARD),
+            cref,
+        }
+    }
+}
+
+impl core::Component for Wizard {
+    #[inline]
+    fn display(&mut self) -> Vec<gfx::DisplayCommand> {
+        theme::paint(self, |o| &mut o.painter)
+    }
+}
+
+impl Wizard {
+    /// Creates a new step at the end of the sequence, returning the root of its subtree.
+    ///
+    /// Steps other than the current one are [frozen](core::Globals::set_frozen), so they're neither updated nor rendered.
+    pub fn add_step<T: core::ComponentFactory>(
+        &mut self,
+        globals: &mut core::Globals,
+    ) -> core::ComponentRef<T> {
+        let step = globals.child(self.cref);
+        if !self.steps.is_empty() {
+            globals.set_frozen(step, true);
+        }
+        self.steps.push((step.into(), None));
+        globals.update(self.cref, core::Repaint::Yes, core::Propagate::No);
+        step
+    }
+
+    /// Sets the hook deciding whether the user may move on from the step at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set_validator(
+        &mut self,
+        index: usize,
+        validator: impl Fn(&core::Globals) -> bool + 'static,
+    ) {
+        self.steps[index].1 = Some(Box::new(validator));
+    }
+
+    /// Moves to the next step, provided the current one is valid and isn't the last.
+    ///
+    /// Returns `true` if the step changed.
+    pub fn next(&mut self, globals: &mut core::Globals) -> bool {
+        if self.is_last() || !self.is_valid(globals) {
+            return false;
+        }
+
+        self.go_to(globals, self.current + 1);
+        true
+    }
+
+    /// Moves to the previous step, without validating the current one.
+    ///
+    /// Returns `true` if the step changed.
+    pub fn back(&mut self, globals: &mut core::Globals) -> bool {
+        if self.current == 0 {
+            return false;
+        }
+
+        self.go_to(globals, self.current - 1);
+        true
+    }
+
+    /// Completes the wizard, provided the last step is current and valid.
+    ///
+    /// Returns `true` if [`on_finished`](Wizard::on_finished) was emitted.
+    pub fn finish(&mut self, globals: &mut core::Globals) -> bool {
+        if self.steps.is_empty() || !self.is_last() || !self.is_valid(globals) {
+            return false;
+        }
+
+        globals.emit(self.on_finished, &());
+        true
+    }
+
+    /// Returns `true` if the validator of the current step (if any) allows moving on.
+    pub fn is_valid(&self, globals: &core::Globals) -> bool {
+        match self.steps.get(self.current) {
+            Some((_, Some(validator))) => validator(globals),
+            _ => true,
+        }
+    }
+
+    /// Returns the index of the current step.
+    #[inline]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the root of the current step's subtree, if there are any steps.
+    #[inline]
+    pub fn current_step(&self) -> Option<core::UntypedComponentRef> {
+        self.steps.get(self.current).map(|(step, _)| *step)
+    }
+
+    /// Returns the number of steps.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    #[inline]
+    pub fn is_first(&self) -> bool {
+        self.current == 0
+    }
+
+    #[inline]
+    pub fn is_last(&self) -> bool {
+        self.current + 1 >= self.steps.len()
+    }
+
+    fn go_to(&mut self, globals: &mut core::Globals, index: usize) {
+        let old = std::mem::replace(&mut self.current, index);
+        globals.set_frozen(self.steps[old].0, true);
+        globals.set_frozen(self.steps[index].0, false);
+        // catch up on whatever the step missed while it was hidden
+        globals.update(
+            self.steps[index].0,
+            core::Repaint::Yes,
+            core::Propagate::Yes,
+        );
+        globals.emit(self.on_step_changed, &kit::ChangeEvent { old, new: index });
+        globals.update(self.cref, core::Repaint::Yes, core::Propagate::No);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{core::ComponentRef, test::MockTheme},
+    };
+
+    struct Step;
+
+    impl core::ComponentFactory for Step {
+        fn new(_globals: &mut core::Globals, _cref: ComponentRef<Self>) -> Self {
+            Step
+        }
+    }
+
+    impl core::Component for Step {}
+
+    #[test]
+    fn only_the_current_step_is_rendered() {
+        let (mut globals, root): (_, ComponentRef<Step>) = core::Globals::new(MockTheme);
+        let wizard: WizardRef = globals.child(root);
+        let (first, second) = globals.with(wizard, |wizard, globals| {
+            let first: ComponentRef<Step> = wizard.add_step(globals);
+            (first, wizard.add_step::<Step>(globals))
+        });
+        assert!(!globals.render(first).is_empty());
+        assert!(globals.render(second).is_empty());
+
+        globals.with(wizard, |wizard, globals| wizard.next(globals));
+        assert!(globals.render(first).is_empty());
+        assert!(!globals.render(second).is_empty());
+    }
+}
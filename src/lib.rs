@@ -1,7 +1,11 @@
 #[macro_use]
 extern crate derivative;
 
+mod arena;
+pub mod commands;
 pub mod core;
 pub mod kit;
+pub mod perf;
 pub mod signal;
+pub mod test;
 pub mod theme;
@@ -0,0 +1,202 @@
+//! Performance counters for the core loops, and (with the `profiler` feature) per-component timings.
+
+#[cfg(feature = "profiler")]
+use crate::core;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Counts of core operations performed by [`Globals`](crate::core::Globals).
+///
+/// Counters accumulate until reset with [`Globals::take_perf_counters`](crate::core::Globals::take_perf_counters), which an app runner would typically call once per frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Counters {
+    /// Number of component updates, including those caused by propagation.
+    pub updates: u64,
+    /// Number of signal emissions.
+    pub emissions: u64,
+    /// Number of components mounted.
+    pub mounts: u64,
+    /// Number of components unmounted.
+    pub unmounts: u64,
+    /// Number of heap allocations made by updates and emissions (including everything they invoke).
+    ///
+    /// This is only counted with [`CountingAllocator`](CountingAllocator) installed as the global allocator, and stays zero otherwise.
+    pub allocations: u64,
+}
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator which counts allocations, feeding [`Counters::allocations`](Counters::allocations).
+///
+/// Install it in the application (or benchmark) with
+/// `#[global_allocator] static ALLOCATOR: vx::perf::CountingAllocator = vx::perf::CountingAllocator;`.
+/// Allocation is otherwise left to the system allocator.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Returns the number of allocations made through [`CountingAllocator`](CountingAllocator) so far, by every thread.
+#[inline]
+pub fn allocations() -> u64 {
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+/// Summary of what the UI tree is holding on to, as returned by [`Globals::memory_report`](crate::core::Globals::memory_report).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Number of mounted component nodes.
+    pub nodes: usize,
+    /// Number of managed listeners attached by components.
+    pub listeners: usize,
+    /// Number of live signals.
+    pub signals: usize,
+    /// Number of observable properties.
+    pub properties: usize,
+    /// Size of the display commands cached for rendering, in bytes.
+    ///
+    /// Only the commands themselves are counted, not heap data owned by them (such as text).
+    pub display_bytes: usize,
+    /// Number of mounted instances of each component type.
+    pub instances: std::collections::HashMap<&'static str, usize>,
+}
+
+/// Accumulated wall time of a component callback.
+#[cfg(feature = "profiler")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timing {
+    /// Number of times the callback was invoked.
+    pub calls: u64,
+    /// Total time spent in the callback, including anything it invoked (such as nested updates).
+    pub total: std::time::Duration,
+    /// Longest single invocation.
+    pub max: std::time::Duration,
+}
+
+#[cfg(feature = "profiler")]
+impl Timing {
+    /// Returns the average time of a single invocation.
+    pub fn mean(&self) -> std::time::Duration {
+        if self.calls == 0 {
+            Default::default()
+        } else {
+            self.total / self.calls as u32
+        }
+    }
+
+    pub(crate) fn record(&mut self, elapsed: std::time::Duration) {
+        self.calls += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+}
+
+/// Timings of the callbacks of a component, or of every component of a type.
+#[cfg(feature = "profiler")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComponentTimings {
+    pub update: Timing,
+    pub display: Timing,
+}
+
+#[cfg(feature = "profiler")]
+impl ComponentTimings {
+    /// Returns the total time spent across all callbacks.
+    #[inline]
+    pub fn total(&self) -> std::time::Duration {
+        self.update.total + self.display.total
+    }
+}
+
+/// Component timings gathered by the profiler, aggregated per type and per instance.
+///
+/// Instances are dropped from the report once unmounted; type aggregates are kept.
+#[cfg(feature = "profiler")]
+#[derive(Debug, Default, Clone)]
+pub struct ProfileReport {
+    pub by_type: std::collections::HashMap<&'static str, ComponentTimings>,
+    pub by_instance: std::collections::HashMap<core::UntypedComponentRef, ComponentTimings>,
+}
+
+#[cfg(feature = "profiler")]
+impl ProfileReport {
+    /// Returns every component type along with its timings, slowest (by total time) first.
+    pub fn slowest_types(&self) -> Vec<(&'static str, ComponentTimings)> {
+        let mut types: Vec<_> = self.by_type.iter().map(|(k, v)| (*k, *v)).collect();
+        types.sort_by_key(|x| std::cmp::Reverse(x.1.total()));
+        types
+    }
+
+    /// Returns every component instance along with its timings, slowest (by total time) first.
+    pub fn slowest_instances(&self) -> Vec<(core::UntypedComponentRef, ComponentTimings)> {
+        let mut instances: Vec<_> = self.by_instance.iter().map(|(k, v)| (*k, *v)).collect();
+        instances.sort_by_key(|x| std::cmp::Reverse(x.1.total()));
+        instances
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        cref: core::UntypedComponentRef,
+        type_name: &'static str,
+        elapsed: std::time::Duration,
+        f: impl Fn(&mut ComponentTimings) -> &mut Timing,
+    ) {
+        f(self.by_type.entry(type_name).or_default()).record(elapsed);
+        f(self.by_instance.entry(cref).or_default()).record(elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{core, test::MockTheme},
+        std::{cell::RefCell, rc::Rc},
+    };
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    struct Root;
+
+    impl core::ComponentFactory for Root {
+        fn new(_globals: &mut core::Globals, _cref: core::ComponentRef<Self>) -> Self {
+            Root
+        }
+    }
+
+    impl core::Component for Root {}
+
+    #[test]
+    fn allocations_in_emissions_are_counted() {
+        let (mut globals, root): (_, core::ComponentRef<Root>) = core::Globals::new(MockTheme);
+        let sref = globals.signal::<u32>();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = log.clone();
+        globals.listen(sref, root, move |_, &event| sink.borrow_mut().push(event));
+        globals.take_perf_counters();
+
+        globals.emit(sref, &0);
+        assert_eq!(*log.borrow(), vec![0]);
+        assert!(globals.perf_counters().allocations > 0);
+    }
+}
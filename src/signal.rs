@@ -1,14 +1,16 @@
 use {
     crate::core,
-    std::{collections::HashMap, rc::Rc},
+    std::{cmp::Reverse, collections::BTreeMap},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct ListenerRef(u64);
+pub struct ListenerRef(Reverse<i32>, u64);
 
 /// Signal type which broadcasts events to listeners.
+///
+/// Listeners are invoked in order of descending priority, then in the order they were added.
 pub struct Signal<T: 'static> {
-    listeners: HashMap<u64, Rc<dyn Fn(&mut core::Globals, &T)>>,
+    listeners: BTreeMap<ListenerRef, Box<dyn FnMut(&mut core::Globals, &T)>>,
     next_id: u64,
 }
 
@@ -24,15 +26,34 @@ impl<T: 'static> Signal<T> {
         }
     }
 
-    /// Adds a listener to the signal.
+    /// Adds a listener to the signal, with the default priority of `0`.
     #[inline]
     pub fn listen(&mut self, listener: impl Fn(&mut core::Globals, &T) + 'static) -> ListenerRef {
-        self.listen_rc(Rc::new(listener))
+        self.insert(0, Box::new(listener))
+    }
+
+    /// Adds a listener to the signal which is invoked before any listeners of lower priority.
+    #[inline]
+    pub fn listen_with_priority(
+        &mut self,
+        priority: i32,
+        listener: impl Fn(&mut core::Globals, &T) + 'static,
+    ) -> ListenerRef {
+        self.insert(priority, Box::new(listener))
+    }
+
+    /// Same as [`listen`](Signal::listen), but the listener may mutate its own captured state (e.g. a counter).
+    #[inline]
+    pub fn listen_mut(
+        &mut self,
+        listener: impl FnMut(&mut core::Globals, &T) + 'static,
+    ) -> ListenerRef {
+        self.insert(0, Box::new(listener))
     }
 
     /// Removes an existing listener from the signal.
     pub fn remove_listener(&mut self, listener: ListenerRef) {
-        self.listeners.remove(&listener.0);
+        self.listeners.remove(&listener);
     }
 
     /// Broadcasts an event to all the listeners.
@@ -41,16 +62,33 @@ impl<T: 'static> Signal<T> {
             (*listener)(globals, event);
         }
     }
+
+    /// Broadcasts an event to the listeners until `stop` returns `true` after one of them, returning whether it did.
+    pub fn emit_until(
+        &mut self,
+        globals: &mut core::Globals,
+        event: &T,
+        stop: impl Fn(&core::Globals) -> bool,
+    ) -> bool {
+        for listener in self.listeners.values_mut() {
+            (*listener)(globals, event);
+            if stop(globals) {
+                return true;
+            }
+        }
+        false
+    }
 }
 
 impl<T: 'static> Signal<T> {
-    pub(crate) fn listen_rc(
+    pub(crate) fn insert(
         &mut self,
-        listener: Rc<dyn Fn(&mut core::Globals, &T)>,
+        priority: i32,
+        listener: Box<dyn FnMut(&mut core::Globals, &T)>,
     ) -> ListenerRef {
-        let id = self.next_id;
+        let id = ListenerRef(Reverse(priority), self.next_id);
         self.next_id += 1;
         self.listeners.insert(id, listener);
-        ListenerRef(id)
+        id
     }
 }
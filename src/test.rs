@@ -0,0 +1,167 @@
+//! Utilities for testing components, themes and painters.
+
+use {
+    crate::{core, theme},
+    reclutch::display as gfx,
+    std::{fmt::Write, path::Path},
+};
+
+/// Environment variable which, when set, makes [`assert_snapshot`](assert_snapshot) overwrite golden files instead of comparing against them.
+pub const UPDATE_SNAPSHOTS_VAR: &str = "VX_UPDATE_SNAPSHOTS";
+
+/// Serializes a display list into a stable, human-readable form suitable for a golden file.
+pub fn snapshot(cmds: &[gfx::DisplayCommand]) -> String {
+    let mut out = String::new();
+    for cmd in cmds {
+        writeln!(out, "{:?}", cmd).unwrap();
+    }
+    out
+}
+
+/// Compares a display list against the golden file at `path`, panicking with a line diff if they differ.
+///
+/// The golden file is (re)written if it doesn't exist yet or if [`UPDATE_SNAPSHOTS_VAR`](UPDATE_SNAPSHOTS_VAR) is set.
+pub fn assert_snapshot(path: impl AsRef<Path>, cmds: &[gfx::DisplayCommand]) {
+    let path = path.as_ref();
+    let actual = snapshot(cmds);
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() || !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("failed to create snapshot directory");
+        }
+        std::fs::write(path, &actual).expect("failed to write snapshot");
+        return;
+    }
+
+    let expected = std::fs::read_to_string(path).expect("failed to read snapshot");
+    if expected != actual {
+        panic!(
+            "snapshot mismatch for {} (set {} to update):\n{}",
+            path.display(),
+            UPDATE_SNAPSHOTS_VAR,
+            diff(&expected, &actual)
+        );
+    }
+}
+
+/// Theme with deterministic, dependency-free output, so widget tests don't depend on the visual details of a real theme.
+///
+/// Every painter emits `Save`, `Clear` (with the [`mock_color`](mock_color) of its painter name), `Restore`,
+/// and reports a [`MOCK_SIZE`](MOCK_SIZE) size hint. Every named color is the [`mock_color`](mock_color) of its name.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MockTheme;
+
+/// Size hint reported by every [`MockTheme`](MockTheme) painter.
+pub const MOCK_SIZE: (f32, f32) = (100.0, 20.0);
+
+/// Derives a stable, opaque color from `name`.
+pub fn mock_color(name: &str) -> gfx::Color {
+    // FNV-1a; stable across platforms and releases, unlike `DefaultHasher`.
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    let channel = |shift: u64| ((hash >> shift) & 0xff) as f32 / 255.0;
+    gfx::Color::new(channel(0), channel(8), channel(16), 1.0)
+}
+
+impl theme::Theme for MockTheme {
+    fn painter(&self, p: &'static str) -> Box<dyn theme::AnyPainter> {
+        Box::new(MockPainter(p))
+    }
+
+    fn color(&self, c: &'static str) -> gfx::Color {
+        mock_color(c)
+    }
+}
+
+struct MockPainter(&'static str);
+
+impl theme::AnyPainter for MockPainter {
+    fn paint(&mut self, _obj: &mut dyn std::any::Any) -> Vec<gfx::DisplayCommand> {
+        vec![
+            gfx::DisplayCommand::Save,
+            gfx::DisplayCommand::Clear(mock_color(self.0)),
+            gfx::DisplayCommand::Restore,
+        ]
+    }
+
+    fn size_hint(&mut self, _obj: &mut dyn std::any::Any) -> gfx::Size {
+        gfx::Size::new(MOCK_SIZE.0, MOCK_SIZE.1)
+    }
+}
+
+/// Applies `ops` random tree mutations beneath `root`, [checking invariants](core::Globals::check_invariants) after each one.
+///
+/// New components are constructed as `T`. Runs are reproducible for a given `seed`, so a failing seed can be replayed.
+/// `root` itself is never unmounted or moved.
+pub fn random_ops<T: core::ComponentFactory>(
+    globals: &mut core::Globals,
+    root: impl Into<core::UntypedComponentRef>,
+    seed: u64,
+    ops: usize,
+) {
+    let root = root.into();
+    let mut rng = XorShift(seed.max(1));
+
+    for _ in 0..ops {
+        let nodes: Vec<_> = std::iter::once(root)
+            .chain(globals.descendants(root))
+            .collect();
+        let node = nodes[rng.below(nodes.len())];
+        let children = globals.untyped_node(node).children().len();
+
+        match rng.below(7) {
+            0 | 1 => {
+                let _: core::ComponentRef<T> = globals.child_at(node, rng.below(children + 1));
+            }
+            2 if node != root => globals.unmount(node),
+            3 if node != root => globals.reverse_unmount(node),
+            4 if node != root => globals.late_unmount(node),
+            5 if children > 1 => globals.move_child(node, rng.below(children), rng.below(children)),
+            6 if node != root => {
+                let candidates: Vec<_> = nodes
+                    .iter()
+                    .copied()
+                    .filter(|x| *x != node && !globals.descendants(node).any(|y| y == *x))
+                    .collect();
+                globals.reparent(node, candidates[rng.below(candidates.len())]);
+            }
+            _ => globals.update(node, core::Repaint::Yes, core::Propagate::Yes),
+        }
+
+        globals.check_invariants();
+    }
+}
+
+/// Minimal deterministic PRNG so the driver doesn't need extra dependencies.
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => writeln!(out, "  {}", e).unwrap(),
+            (e, a) => {
+                if let Some(e) = e {
+                    writeln!(out, "- {}", e).unwrap();
+                }
+                if let Some(a) = a {
+                    writeln!(out, "+ {}", a).unwrap();
+                }
+            }
+        }
+    }
+    out
+}
@@ -39,6 +39,10 @@ impl<P: TypedPainter> AnyPainter for P {
 pub trait Theme {
     fn painter(&self, p: &'static str) -> Box<dyn AnyPainter>;
     fn color(&self, c: &'static str) -> gfx::Color;
+
+    /// Invoked when the [accessibility preferences](crate::core::Globals::accessibility_prefs) change, so the theme can switch palettes.
+    #[inline]
+    fn accessibility_changed(&mut self, _prefs: crate::core::AccessibilityPrefs) {}
 }
 
 pub fn get_painter<O: 'static>(theme: &dyn Theme, p: &'static str) -> Painter<O> {
@@ -67,7 +71,10 @@ pub mod painters {
     //! For a theme to support `kit`, it must implement all of these.
 
     pub const BUTTON: &str = "button";
+    pub const FRAMES: &str = "frames";
     pub const LABEL: &str = "label";
+    pub const SPINNER: &str = "spinner";
+    pub const WIZARD: &str = "wizard";
 }
 
 pub mod colors {